@@ -4,7 +4,7 @@ set -e
 
 rm -Rf artifacts
 mkdir artifacts
-cargo build --release --target wasm32-unknown-unknown
+cargo build --release --lib --target wasm32-unknown-unknown
 
 mv target/wasm32-unknown-unknown/release/*.wasm artifacts/
 rm -Rf target
@@ -0,0 +1,152 @@
+//! Drives random JSON execute messages from random senders at random
+//! block times through the contract's entry points. The contract may
+//! reject any message, but it must never panic, never send more than
+//! it holds, never pay the vestee more than the total of the vest,
+//! never pay out more IBC refunds than it sent over IBC and always
+//! report its invariants as holding.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{
+    coins, from_json, BankMsg, CosmosMsg, Decimal, DistributionMsg, IbcMsg, Uint128,
+};
+use libfuzzer_sys::fuzz_target;
+use tc_vesting::contract::{execute, instantiate, query};
+use tc_vesting::denom::UncheckedDenom;
+use tc_vesting::msg::{ExecuteMsg, InstantiateMsg, InvariantsResponse, QueryMsg};
+use tc_vesting::payment::{KeeperConfig, Schedule};
+use tc_vesting::state::PAYMENT;
+
+const DENOM: &str = "uluna";
+const OWNER: &str = "owner";
+const RECIPIENT: &str = "recipient";
+const SENDERS: [&str; 5] = [OWNER, RECIPIENT, "guardian", "keeper", "anyone"];
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    total: u64,
+    /// A JSON `Schedule`, `saturating_linear` if it does not parse.
+    schedule: String,
+    duration_seconds: u32,
+    /// The start of the schedule relative to instantiation.
+    start_offset_seconds: Option<i32>,
+    cancel_notice_seconds: Option<u32>,
+    keeper_tip_percent: Option<u8>,
+    claim_deadline_seconds: Option<u32>,
+    steps: Vec<Step>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Step {
+    advance_seconds: u32,
+    sender: u8,
+    /// The number of tokens the Community Pool sends to the contract
+    /// before the message is executed.
+    spend: u64,
+    /// A JSON `ExecuteMsg`.
+    msg: String,
+}
+
+fuzz_target!(|input: Input| {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let contract = env.contract.address.clone();
+
+    let start_time = input.start_offset_seconds.map(|offset| {
+        let magnitude = u64::from(offset.unsigned_abs());
+        if offset < 0 {
+            env.block.time.minus_seconds(magnitude)
+        } else {
+            env.block.time.plus_seconds(magnitude)
+        }
+    });
+    let msg = InstantiateMsg {
+        owner: OWNER.to_string(),
+        recipient: RECIPIENT.to_string(),
+        title: "title".to_string(),
+        description: None,
+        total: Uint128::from(input.total),
+        denom: UncheckedDenom::Native(DENOM.to_string()),
+        schedule: from_json(input.schedule.as_bytes()).unwrap_or(Schedule::SaturatingLinear),
+        start_time,
+        vesting_duration_seconds: input.duration_seconds.into(),
+        keeper: input.keeper_tip_percent.map(|percent| KeeperConfig {
+            tip_rate: Decimal::percent(percent.into()),
+            max_tip: Uint128::new(1_000),
+            min_interval_seconds: 60,
+        }),
+        cancel_notice_seconds: input.cancel_notice_seconds.map(u64::from),
+        guardian: Some("guardian".to_string()),
+        allow_ownership_transfer: Some(true),
+        claim_deadline_seconds: input.claim_deadline_seconds.map(u64::from),
+    };
+    if instantiate(deps.as_mut(), env.clone(), mock_info(OWNER, &[]), msg).is_err() {
+        return;
+    }
+
+    let mut balance = Uint128::zero();
+    let mut to_vestee = Uint128::zero();
+    let mut sent_over_ibc = Uint128::zero();
+    let mut refunded = Uint128::zero();
+    for step in input.steps {
+        env.block.time = env.block.time.plus_seconds(step.advance_seconds.into());
+        env.block.height += u64::from(step.advance_seconds) / 5;
+        balance += Uint128::from(step.spend);
+        deps.querier.update_balance(&contract, coins(balance.u128(), DENOM));
+
+        let Ok(msg) = from_json::<ExecuteMsg>(step.msg.as_bytes()) else {
+            continue;
+        };
+        let sender = SENDERS[usize::from(step.sender) % SENDERS.len()];
+        let Ok(res) = execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), msg) else {
+            continue;
+        };
+
+        // transfers are never refunded here, so a claimed refund is
+        // paid from surplus balance and must not exceed the IBC payouts
+        let refund = res
+            .attributes
+            .iter()
+            .any(|a| a.key == "method" && a.value == "claim_ibc_refund");
+        for sub in res.messages {
+            // keeper tips are deducted from the vestee's share
+            let (vestee, amount) = match sub.msg {
+                CosmosMsg::Bank(BankMsg::Send { amount, .. }) => (true, amount),
+                CosmosMsg::Ibc(IbcMsg::Transfer { amount, .. }) => {
+                    sent_over_ibc += amount.amount;
+                    (true, vec![amount])
+                }
+                CosmosMsg::Distribution(DistributionMsg::FundCommunityPool { amount }) => {
+                    (false, amount)
+                }
+                msg => panic!("unexpected message {msg:?}"),
+            };
+            assert!(amount.iter().all(|c| c.denom == DENOM), "wrong denom {amount:?}");
+            let amount: Uint128 = amount.iter().map(|c| c.amount).sum();
+            assert!(amount <= balance, "sent {amount} holding {balance}");
+            balance -= amount;
+            if refund {
+                refunded += amount;
+            } else if vestee {
+                to_vestee += amount;
+            }
+        }
+        assert!(
+            refunded <= sent_over_ibc,
+            "refunded {refunded} of {sent_over_ibc} sent over IBC"
+        );
+        deps.querier.update_balance(&contract, coins(balance.u128(), DENOM));
+
+        let vest = PAYMENT.get_vest(&deps.storage).unwrap();
+        assert!(
+            to_vestee <= vest.total(),
+            "paid {to_vestee} of a total of {}",
+            vest.total()
+        );
+        let invariants: InvariantsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Invariants {}).unwrap()).unwrap();
+        assert!(invariants.ok, "{:?}", invariants.violations);
+    }
+});
@@ -0,0 +1,46 @@
+use std::env::current_dir;
+use std::fs::{create_dir_all, write};
+
+use cosmwasm_schema::{export_schema, generate_api, schema_for};
+use tc_vesting::events::{
+    CancelEvent, ExtendEvent, FreezeEvent, ReduceEvent, RevokeCancelEvent, ScheduleCancelEvent,
+    TransferEvent, UnfreezeEvent,
+};
+use tc_vesting::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+fn main() {
+    let out_dir = current_dir().unwrap().join("schema");
+    create_dir_all(&out_dir).unwrap();
+
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+    }
+    .render();
+    write(
+        out_dir.join(concat!(env!("CARGO_PKG_NAME"), ".json")),
+        api.to_string().unwrap(),
+    )
+    .unwrap();
+
+    let raw_dir = out_dir.join("raw");
+    create_dir_all(&raw_dir).unwrap();
+    for (filename, json) in api.to_schema_files().unwrap() {
+        write(raw_dir.join(filename), json).unwrap();
+    }
+
+    // Events are not part of the contract API, but indexers consume
+    // them, so their schemas are exported alongside.
+    let events_dir = out_dir.join("events");
+    create_dir_all(&events_dir).unwrap();
+    export_schema(&schema_for!(TransferEvent), &events_dir);
+    export_schema(&schema_for!(CancelEvent), &events_dir);
+    export_schema(&schema_for!(ScheduleCancelEvent), &events_dir);
+    export_schema(&schema_for!(RevokeCancelEvent), &events_dir);
+    export_schema(&schema_for!(ReduceEvent), &events_dir);
+    export_schema(&schema_for!(ExtendEvent), &events_dir);
+    export_schema(&schema_for!(FreezeEvent), &events_dir);
+    export_schema(&schema_for!(UnfreezeEvent), &events_dir);
+}
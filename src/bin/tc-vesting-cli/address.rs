@@ -0,0 +1,90 @@
+//! Predicts the addresses of contracts instantiated with
+//! instantiate2, so that a Community Pool spend to a payment can be
+//! proposed together with its instantiation.
+
+use bech32::{Bech32, Hrp};
+use cosmwasm_std::{instantiate2_address, CanonicalAddr, StdError, StdResult};
+
+/// Gets the address of the contract CREATOR instantiates with
+/// instantiate2 from the code with CHECKSUM and SALT.
+pub fn predict_address(
+    checksum: &[u8],
+    creator: &CanonicalAddr,
+    salt: &[u8],
+) -> StdResult<CanonicalAddr> {
+    instantiate2_address(checksum, creator, salt)
+        .map_err(|e| StdError::generic_err(format!("instantiate2 address: {e}")))
+}
+
+/// Like `predict_address`, but for bech32 addresses so that it can be
+/// used offline, where no `Api` is available. The predicted address
+/// has the same prefix as CREATOR.
+pub fn predict_bech32_address(checksum: &[u8], creator: &str, salt: &[u8]) -> StdResult<String> {
+    let (hrp, creator) = bech32::decode(creator)
+        .map_err(|e| StdError::generic_err(format!("invalid bech32 address {creator}: {e}")))?;
+    let hrp = Hrp::parse(&hrp.to_lowercase()).expect("a decoded prefix is valid");
+    let address = predict_address(checksum, &creator.into(), salt)?;
+    bech32::encode::<Bech32>(hrp, address.as_slice())
+        .map_err(|e| StdError::generic_err(format!("bech32 address: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::HexBinary;
+
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        HexBinary::from_hex(s).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_predict_address() {
+        // The first of wasmd's instantiate2 address test vectors.
+        let checksum = hex("13a1fc994cc6d1c81b746ee0c0ff6f90043875e0bf1d9be6b7d779fc978dc2a5");
+        let creator = CanonicalAddr::from(hex("9999999999aaaaaaaaaabbbbbbbbbbcccccccccc"));
+        assert_eq!(
+            predict_address(&checksum, &creator, b"a").unwrap(),
+            CanonicalAddr::from(hex(
+                "5e865d3e45ad3e961f77fd77d46543417ced44d924dc3e079b5415ff6775f847"
+            ))
+        );
+
+        // The same vector and the one for a 32 byte creator in bech32.
+        let bech32_creator = "purple1nxvenxve42424242hwamhwamenxvenxvhxf2py";
+        assert_eq!(
+            predict_bech32_address(&checksum, bech32_creator, b"a").unwrap(),
+            "purple1t6r960j945lfv8mhl4mage2rg97w63xeynwrupum2s2l7em4lprs9ce5hk"
+        );
+        assert_eq!(
+            predict_bech32_address(&checksum, &bech32_creator.to_uppercase(), b"a").unwrap(),
+            "purple1t6r960j945lfv8mhl4mage2rg97w63xeynwrupum2s2l7em4lprs9ce5hk"
+        );
+        assert_eq!(
+            predict_bech32_address(
+                &checksum,
+                "purple1nxvenxve42424242hwamhwamenxvenxvmhwamhwaamhwamhwlllsatsy6m",
+                b"a"
+            )
+            .unwrap(),
+            "purple1juj7jn6j3k9h35euyhealntquc2zmzlxp2ek76jmtypkl4g4vrdsfwmwxk"
+        );
+
+        // Corrupted checksum and mixed case.
+        assert!(predict_bech32_address(
+            &checksum,
+            "terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95m",
+            b"a"
+        )
+        .is_err());
+        assert!(predict_bech32_address(
+            &checksum,
+            "Terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n",
+            b"a"
+        )
+        .is_err());
+
+        assert!(predict_address(&checksum[1..], &creator, b"a").is_err());
+        assert!(predict_address(&checksum, &creator, b"").is_err());
+    }
+}
@@ -0,0 +1,420 @@
+//! Builds an `InstantiateMsg` from dates and amounts in whole tokens
+//! and validates it like the contract does. The message is printed to
+//! stdout, a release calendar and a vesting chart for review are
+//! printed to stderr.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmwasm_std::{to_json_string, Addr, Timestamp, Uint128};
+use tc_vesting::denom::{CheckedDenom, UncheckedDenom};
+use tc_vesting::msg::InstantiateMsg;
+use tc_vesting::payment::{Schedule, Vest, VestInit};
+
+use crate::{Args, GOVERNANCE};
+
+const MICRO: u128 = 1_000_000;
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+const CHART_WIDTH: u64 = 60;
+const CHART_HEIGHT: u64 = 12;
+
+/// A calendar date and time in UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    seconds: u64,
+}
+
+impl DateTime {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let err = || format!("invalid date `{s}`, expected YYYY-MM-DD[THH:MM[:SS]]");
+        let (date, time) = s.split_once('T').unwrap_or((s, "00:00"));
+        let date: Vec<&str> = date.split('-').collect();
+        let time: Vec<&str> = time.split(':').collect();
+        if date.len() != 3 || !(2..=3).contains(&time.len()) {
+            return Err(err());
+        }
+        let year: i64 = date[0].parse().map_err(|_| err())?;
+        let month: u32 = date[1].parse().map_err(|_| err())?;
+        let day: u32 = date[2].parse().map_err(|_| err())?;
+        let mut seconds = 0;
+        for (part, factor) in time.iter().zip([3600, 60, 1]) {
+            seconds += part.parse::<u64>().map_err(|_| err())? * factor;
+        }
+        if year < 1970
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || seconds >= SECONDS_PER_DAY
+        {
+            return Err(err());
+        }
+        Ok(Self {
+            year,
+            month,
+            day,
+            seconds,
+        })
+    }
+
+    pub fn from_unix(t: u64) -> Self {
+        let (year, month, day) = civil_from_days((t / SECONDS_PER_DAY) as i64);
+        Self {
+            year,
+            month,
+            day,
+            seconds: t % SECONDS_PER_DAY,
+        }
+    }
+
+    pub fn unix(&self) -> u64 {
+        days_from_civil(self.year, self.month, self.day) as u64 * SECONDS_PER_DAY + self.seconds
+    }
+
+    /// Adds N calendar months. Days past the end of the resulting
+    /// month are clamped, e.g. Jan 31 + 1 month is Feb 28.
+    fn add_months(&self, n: u64) -> Self {
+        let months = self.year * 12 + (self.month - 1) as i64 + n as i64;
+        let year = months / 12;
+        let month = (months % 12) as u32 + 1;
+        Self {
+            year,
+            month,
+            day: self.day.min(days_in_month(year, month)),
+            seconds: self.seconds,
+        }
+    }
+
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Formats the date, and the time of day if it is not midnight.
+    pub fn to_short_string(self) -> String {
+        match self.seconds {
+            0 => self.date(),
+            s => format!("{} {:02}:{:02}:{:02}", self.date(), s / 3600, s / 60 % 60, s % 60),
+        }
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+///
+/// <http://howardhinnant.github.io/date_algorithms.html>
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = ((month + 9) % 12) as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Parses an amount of whole tokens with up to six decimals into
+/// micro units.
+pub fn parse_amount(s: &str) -> Result<Uint128, String> {
+    let err = || format!("invalid amount `{s}`");
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    if whole.is_empty()
+        || frac.len() > 6
+        || !whole.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return Err(err());
+    }
+    let whole: u128 = whole.parse().map_err(|_| err())?;
+    let frac: u128 = format!("{frac:0<6}").parse().map_err(|_| err())?;
+    whole
+        .checked_mul(MICRO)
+        .and_then(|w| w.checked_add(frac))
+        .map(Uint128::new)
+        .ok_or_else(err)
+}
+
+pub fn format_amount(amount: Uint128) -> String {
+    let whole = amount.u128() / MICRO;
+    let frac = amount.u128() % MICRO;
+    if frac == 0 {
+        whole.to_string()
+    } else {
+        format!("{whole}.{frac:06}").trim_end_matches('0').to_string()
+    }
+}
+
+pub fn unit(denom: &str) -> &str {
+    match denom {
+        "uluna" => "LUNC",
+        "uusd" => "USTC",
+        other => other,
+    }
+}
+
+/// Builds a piecewise linear schedule starting one second after the
+/// vest start time, see `Schedule::PiecewiseLinear`. Returns the
+/// schedule together with its duration.
+///
+/// In tranche mode, `total / months` is released at the end of every
+/// month, and tranches before the cliff are released at the cliff. In
+/// linear mode, tokens vest continuously and the amount vested until
+/// the cliff is released at the cliff.
+fn build_schedule(
+    start: DateTime,
+    total: Uint128,
+    months: u64,
+    cliff_months: u64,
+    linear: bool,
+) -> (Schedule, u64) {
+    let offset = |month: u64| start.add_months(month).unix() - start.unix();
+    let cliff_months = cliff_months.min(months);
+    let mut steps = vec![(1, Uint128::zero())];
+    let mut release = |seconds: u64, vested: Uint128| {
+        let previous = steps.last().unwrap().1;
+        steps.push((seconds, previous));
+        steps.push((seconds + 1, vested));
+    };
+    if linear {
+        if cliff_months > 0 {
+            release(
+                offset(cliff_months),
+                total.multiply_ratio(cliff_months, months),
+            );
+        }
+        if cliff_months < months {
+            steps.push((offset(months) + 1, total));
+        }
+    } else {
+        for month in cliff_months.max(1)..=months {
+            release(offset(month), total.multiply_ratio(month, months));
+        }
+    }
+    let duration = steps.last().unwrap().0;
+    (Schedule::PiecewiseLinear(steps), duration)
+}
+
+fn chart(vest: &Vest, denom: &str) -> String {
+    let start = vest.start_time().seconds();
+    let end = vest.end_time().seconds();
+    let total = vest.total();
+    let rows: Vec<u64> = (0..CHART_WIDTH)
+        .map(|column| {
+            let t = start + (end - start) * column / (CHART_WIDTH - 1);
+            let vested = vest.vested(Timestamp::from_seconds(t));
+            vested.multiply_ratio(CHART_HEIGHT, total).u128() as u64
+        })
+        .collect();
+
+    let label_width = format_amount(total).len();
+    let mut out = String::new();
+    for row in (1..=CHART_HEIGHT).rev() {
+        let label = match row {
+            r if r == CHART_HEIGHT => format_amount(total),
+            r if r == CHART_HEIGHT / 2 => format_amount(total.multiply_ratio(1u128, 2u128)),
+            _ => String::new(),
+        };
+        let line: String = rows
+            .iter()
+            .map(|&height| if height >= row { '#' } else { ' ' })
+            .collect();
+        out += &format!("{label:>label_width$} |{line}\n");
+    }
+    out += &format!("{:>label_width$} +{}\n", 0, "-".repeat(CHART_WIDTH as usize));
+    let from = DateTime::from_unix(start + 1).date();
+    let to = DateTime::from_unix(end).date();
+    out += &format!(
+        "{:label_width$}  {from}{to:>width$}\n",
+        "",
+        width = CHART_WIDTH as usize - from.len()
+    );
+    out += &format!("{:label_width$}  vested {} over time\n", "", unit(denom));
+    out
+}
+
+/// Converts MSG into the arguments the contract initializes the
+/// payment with. `start_time` defaults to NOW. Addresses can only be
+/// validated on chain and are taken as is.
+pub fn vest_init(msg: &InstantiateMsg, now: Timestamp) -> Result<VestInit, String> {
+    let denom = match &msg.denom {
+        UncheckedDenom::Native(denom) => CheckedDenom::Native(denom.clone()),
+        UncheckedDenom::Cw20(_) => return Err("only native denoms can be vested".to_string()),
+    };
+    Ok(VestInit {
+        total: msg.total,
+        schedule: msg.schedule.clone(),
+        start_time: msg.start_time.unwrap_or(now),
+        duration_seconds: msg.vesting_duration_seconds,
+        denom,
+        recipient: Addr::unchecked(&msg.recipient),
+        title: msg.title.clone(),
+        description: msg.description.clone(),
+        keeper: msg.keeper.clone(),
+        cancel_notice_seconds: msg.cancel_notice_seconds.unwrap_or_default(),
+        guardian: msg.guardian.as_ref().map(Addr::unchecked),
+        claim_deadline_seconds: msg.claim_deadline_seconds,
+    })
+}
+
+/// Runs the same validation on MSG as the contract does on
+/// instantiation now. Returns the resulting vest.
+pub fn validate(msg: &InstantiateMsg) -> Result<Vest, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    let init = vest_init(msg, Timestamp::from_seconds(now))?;
+    if init.start_time.plus_seconds(init.duration_seconds).seconds() <= now {
+        return Err("the schedule ends in the past".to_string());
+    }
+    Vest::new(init).map_err(|e| e.to_string())
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let recipient = args.required("--recipient")?.to_string();
+    let title = args.required("--title")?.to_string();
+    let total = parse_amount(args.required("--total")?)?;
+    let start = DateTime::parse(args.required("--start")?)?;
+    let months = args
+        .number("--months")?
+        .filter(|m| *m > 0)
+        .ok_or("`--months` must be a positive number")?;
+    let cliff_months = args.number("--cliff-months")?.unwrap_or_default();
+    if cliff_months > months {
+        return Err("`--cliff-months` must not exceed `--months`".to_string());
+    }
+    let denom = args.get("--denom").unwrap_or("uluna").to_string();
+    let cancel_notice_seconds = args
+        .number("--cancel-notice-days")?
+        .map(|days| days * SECONDS_PER_DAY);
+    let claim_deadline_seconds = args
+        .number("--claim-deadline-days")?
+        .map(|days| days * SECONDS_PER_DAY);
+    let guardian = args.get("--guardian").map(str::to_string);
+
+    let (schedule, duration) =
+        build_schedule(start, total, months, cliff_months, args.flag("--linear"));
+    let msg = InstantiateMsg {
+        owner: args.get("--owner").unwrap_or(GOVERNANCE).to_string(),
+        recipient,
+        title,
+        description: args.get("--description").map(str::to_string),
+        total,
+        denom: UncheckedDenom::Native(denom.clone()),
+        schedule,
+        // The schedule starts at second one, see `Schedule::PiecewiseLinear`.
+        start_time: Some(Timestamp::from_seconds(start.unix() - 1)),
+        vesting_duration_seconds: duration,
+        keeper: None,
+        cancel_notice_seconds,
+        guardian,
+        allow_ownership_transfer: args
+            .flag("--allow-ownership-transfer")
+            .then_some(true),
+        claim_deadline_seconds,
+    };
+    let vest = validate(&msg)?;
+
+    eprintln!("Releases (UTC):");
+    for release in vest.releases() {
+        eprintln!(
+            "  {}  {:>20} {}",
+            DateTime::from_unix(release.time.seconds()).date(),
+            format_amount(release.amount),
+            unit(&denom)
+        );
+    }
+    eprintln!();
+    eprint!("{}", chart(&vest, &denom));
+    println!("{}", to_json_string(&msg).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        let date = DateTime::parse("2025-01-31").unwrap();
+        assert_eq!(date.unix(), 1_738_281_600);
+        assert_eq!(DateTime::from_unix(date.unix()), date);
+        assert_eq!(date.add_months(1).date(), "2025-02-28");
+        assert_eq!(date.add_months(13).date(), "2026-02-28");
+        assert_eq!(
+            DateTime::parse("2024-02-29T12:30").unwrap().unix(),
+            1_709_209_800
+        );
+        assert!(DateTime::parse("2025-02-29").is_err());
+        assert!(DateTime::parse("2025-01-01T24:00").is_err());
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(parse_amount("1000000").unwrap(), Uint128::new(1_000_000_000_000));
+        assert_eq!(parse_amount("0.5").unwrap(), Uint128::new(500_000));
+        assert!(parse_amount("0.0000001").is_err());
+        assert!(parse_amount("-1").is_err());
+        assert_eq!(format_amount(Uint128::new(1_500_000)), "1.5");
+        assert_eq!(format_amount(Uint128::new(2_000_000)), "2");
+    }
+
+    #[test]
+    fn test_readme_schedule() {
+        // Two tranches released 30 days apart, as in the README.
+        let start = DateTime::parse("2025-04-01").unwrap();
+        let (schedule, duration) =
+            build_schedule(start, Uint128::new(1_000_000_000_000), 2, 0, false);
+        assert_eq!(duration, 61 * SECONDS_PER_DAY + 1);
+        assert_eq!(
+            schedule,
+            Schedule::PiecewiseLinear(vec![
+                (1, Uint128::zero()),
+                (30 * SECONDS_PER_DAY, Uint128::zero()),
+                (30 * SECONDS_PER_DAY + 1, Uint128::new(500_000_000_000)),
+                (61 * SECONDS_PER_DAY, Uint128::new(500_000_000_000)),
+                (61 * SECONDS_PER_DAY + 1, Uint128::new(1_000_000_000_000)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_cliff() {
+        let start = DateTime::parse("2025-01-01").unwrap();
+        let total = Uint128::new(12);
+        let (tranches, _) = build_schedule(start, total, 12, 3, false);
+        let (linear, _) = build_schedule(start, total, 12, 3, true);
+        for schedule in [tranches, linear] {
+            let curve = schedule.clone().into_curve(total, 0).unwrap();
+            let cliff = start.add_months(3).unix() - start.unix();
+            assert_eq!(curve.value(cliff), Uint128::zero());
+            assert_eq!(curve.value(cliff + 1), Uint128::new(3));
+        }
+    }
+}
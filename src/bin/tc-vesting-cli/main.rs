@@ -0,0 +1,151 @@
+//! Offline tooling to launch vesting payments through governance,
+//! without computing nanosecond timestamps, piecewise schedules or
+//! proposal messages by hand.
+//!
+//! ```text
+//! tc-vesting-cli instantiate --recipient terra1... --title "Payroll" \
+//!     --total 1000000 --start 2025-02-01 --months 12 --cliff-months 3 > msg.json
+//! tc-vesting-cli proposal --msg msg.json --code-id 42 --salt payroll-2025 \
+//!     --checksum 13a1fc99... --deposit 10000000uluna > proposal.json
+//! tc-vesting-cli simulate --msg msg.json --script scenario.json
+//! ```
+
+use std::collections::HashMap;
+use std::process::exit;
+
+mod address;
+mod instantiate;
+mod proposal;
+mod simulate;
+
+/// The Terra Classic governance module account.
+const GOVERNANCE: &str = "terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n";
+
+const USAGE: &str = "\
+Usage: tc-vesting-cli <COMMAND> [OPTIONS]
+
+Commands:
+  instantiate    build and validate an instantiate message
+  proposal       bundle instantiation, funding and activation into a gov proposal
+  simulate       replay funding, distributions and cancellations offline
+
+Options of `instantiate`, required:
+  --recipient <ADDR>            the vestee's address
+  --title <TEXT>                the title of the payment
+  --total <AMOUNT>              the total amount in whole tokens, e.g. 1000000 or 0.5
+  --start <DATE>                the start date in UTC, YYYY-MM-DD or YYYY-MM-DDTHH:MM[:SS]
+  --months <N>                  the length of the schedule in calendar months
+
+Options of `instantiate`, optional:
+  --owner <ADDR>                the owner, defaults to the governance module account
+  --description <TEXT>          a description of the payment
+  --denom <DENOM>               the native denom, defaults to uluna
+  --cliff-months <N>            nothing is released before this many months
+  --linear                      vest continuously instead of in monthly tranches
+  --cancel-notice-days <N>      the notice period of cancellations
+  --guardian <ADDR>             an address that can freeze distributions
+  --claim-deadline-days <N>     days after the end after which unclaimed funds return
+  --allow-ownership-transfer    allow the owner to hand over the contract
+
+Options of `proposal`, required:
+  --msg <FILE>                  the instantiate message, e.g. as built by `instantiate`
+  --code-id <N>                 the code id of the vesting contract
+  --salt <TEXT>                 the instantiate2 salt
+  --checksum <HEX>              the checksum of the vesting contract's code
+  --deposit <COINS>             the proposal deposit, e.g. 10000000uluna
+
+Options of `proposal`, optional:
+  --contract <ADDR>             the expected contract address, checked against the prediction
+  --title <TEXT>                the proposal title, defaults to one derived from the message
+  --summary <TEXT>              the proposal summary, defaults to one derived from the message
+  --metadata <TEXT>             the proposal metadata, e.g. an IPFS link to a forum post
+  --label <TEXT>                the contract label, defaults to the payment title
+  --admin <ADDR>                the wasm admin, defaults to the governance module account
+
+Options of `simulate`:
+  --msg <FILE>                  the instantiate message, with a `start_time`
+  --script <FILE>               a JSON list of steps, e.g.
+                                [{\"at\": \"2025-03-01\", \"action\": {\"cancel\": {}}}],
+                                with the actions fund, distribute, cancel,
+                                finalize_cancel, pause and unpause. Without a
+                                script, shows the payouts of a cancellation at
+                                each release
+";
+
+struct Args {
+    values: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        const FLAGS: [&str; 3] = ["--linear", "--allow-ownership-transfer", "--help"];
+        let mut values = HashMap::new();
+        let mut flags = vec![];
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                return Err(format!("unexpected argument `{arg}`"));
+            }
+            if FLAGS.contains(&arg.as_str()) {
+                flags.push(arg);
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for `{arg}`"))?;
+            values.insert(arg, value);
+        }
+        Ok(Self { values, flags })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.get(name).ok_or_else(|| format!("`{name}` is required"))
+    }
+
+    fn number(&self, name: &str) -> Result<Option<u64>, String> {
+        self.get(name)
+            .map(|v| v.parse().map_err(|_| format!("`{name}` must be a number")))
+            .transpose()
+    }
+}
+
+fn main() {
+    let mut argv = std::env::args().skip(1);
+    let command = argv.next().unwrap_or_default();
+    let args = match Args::parse(argv) {
+        Ok(args) if !args.flag("--help") => args,
+        Ok(_) => {
+            print!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            exit(2);
+        }
+    };
+    let result = match command.as_str() {
+        "instantiate" => instantiate::run(&args),
+        "proposal" => proposal::run(&args),
+        "simulate" => simulate::run(&args),
+        "--help" | "help" => {
+            print!("{USAGE}");
+            return;
+        }
+        other => {
+            eprintln!("error: unknown command `{other}`\n\n{USAGE}");
+            exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        exit(1);
+    }
+}
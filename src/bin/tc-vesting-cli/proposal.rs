@@ -0,0 +1,251 @@
+//! Bundles the launch of a payment into a single governance proposal
+//! for the gov v1 module: the contract is instantiated with
+//! instantiate2, funded by a Community Pool spend to its predicted
+//! address and activated with `Fund`. Messages of a proposal execute
+//! atomically and in order, so a wrong address fails the whole
+//! proposal instead of sending funds elsewhere.
+//!
+//! The address is predicted from the code checksum, the governance
+//! module account as creator and the salt. The proposal JSON is
+//! printed to stdout and can be submitted with `terrad tx gov
+//! submit-proposal`.
+
+use std::fs::read;
+
+use cosmwasm_schema::serde::Serialize;
+use cosmwasm_std::{from_json, to_json_string, Binary, Coin, HexBinary, Uint64};
+use tc_vesting::denom::UncheckedDenom;
+use tc_vesting::msg::{ExecuteMsg, InstantiateMsg};
+
+use crate::address::predict_bech32_address;
+use crate::instantiate::{format_amount, unit, validate};
+use crate::{Args, GOVERNANCE};
+
+#[derive(Serialize)]
+#[serde(crate = "cosmwasm_schema::serde")]
+struct Proposal {
+    messages: Vec<ProposalMsg>,
+    metadata: String,
+    deposit: String,
+    title: String,
+    summary: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "cosmwasm_schema::serde", tag = "@type")]
+enum ProposalMsg {
+    #[serde(rename = "/cosmwasm.wasm.v1.MsgInstantiateContract2")]
+    Instantiate2 {
+        sender: String,
+        admin: String,
+        code_id: Uint64,
+        label: String,
+        msg: Box<InstantiateMsg>,
+        funds: Vec<Coin>,
+        salt: Binary,
+        fix_msg: bool,
+    },
+    #[serde(rename = "/cosmos.distribution.v1beta1.MsgCommunityPoolSpend")]
+    CommunityPoolSpend {
+        authority: String,
+        recipient: String,
+        amount: Vec<Coin>,
+    },
+    #[serde(rename = "/cosmwasm.wasm.v1.MsgExecuteContract")]
+    Execute {
+        sender: String,
+        contract: String,
+        msg: ExecuteMsg,
+        funds: Vec<Coin>,
+    },
+}
+
+/// Builds the proposal to instantiate MSG at CONTRACT, fund it from
+/// the Community Pool and activate the vesting schedule.
+fn build_proposal(
+    msg: InstantiateMsg,
+    code_id: u64,
+    salt: &str,
+    contract: &str,
+    args: &Args,
+) -> Result<Proposal, String> {
+    let denom = match &msg.denom {
+        UncheckedDenom::Native(denom) => denom.clone(),
+        UncheckedDenom::Cw20(_) => return Err("only native denoms can be vested".to_string()),
+    };
+    let title = args
+        .get("--title")
+        .map_or_else(|| format!("Vesting payment: {}", msg.title), str::to_string);
+    let summary = args.get("--summary").map_or_else(
+        || {
+            format!(
+                "Instantiates a vesting contract at {contract} paying {} {} to {}, \
+                 funds it from the Community Pool and activates the vesting schedule.{}",
+                format_amount(msg.total),
+                unit(&denom),
+                msg.recipient,
+                msg.description
+                    .as_ref()
+                    .map_or_else(String::new, |d| format!("\n\n{d}")),
+            )
+        },
+        str::to_string,
+    );
+
+    Ok(Proposal {
+        messages: vec![
+            ProposalMsg::Instantiate2 {
+                sender: GOVERNANCE.to_string(),
+                admin: args.get("--admin").unwrap_or(GOVERNANCE).to_string(),
+                code_id: code_id.into(),
+                label: args.get("--label").unwrap_or(&msg.title).to_string(),
+                funds: vec![],
+                salt: Binary::from(salt.as_bytes()),
+                fix_msg: false,
+                msg: Box::new(msg.clone()),
+            },
+            ProposalMsg::CommunityPoolSpend {
+                authority: GOVERNANCE.to_string(),
+                recipient: contract.to_string(),
+                amount: vec![Coin::new(msg.total.u128(), denom)],
+            },
+            ProposalMsg::Execute {
+                sender: GOVERNANCE.to_string(),
+                contract: contract.to_string(),
+                msg: ExecuteMsg::Fund {},
+                funds: vec![],
+            },
+        ],
+        metadata: args.get("--metadata").unwrap_or_default().to_string(),
+        deposit: args.required("--deposit")?.to_string(),
+        title,
+        summary,
+    })
+}
+
+/// Gets the address the governance module will instantiate the
+/// contract at, predicted from `--checksum` or given by `--contract`.
+/// If both are given, they have to match.
+fn contract_address(args: &Args, salt: &str) -> Result<String, String> {
+    let predicted = args
+        .get("--checksum")
+        .map(|checksum| {
+            let checksum = HexBinary::from_hex(checksum).map_err(|e| e.to_string())?;
+            predict_bech32_address(&checksum, GOVERNANCE, salt.as_bytes())
+                .map_err(|e| e.to_string())
+        })
+        .transpose()?;
+    match (predicted, args.get("--contract")) {
+        (Some(predicted), Some(contract)) if predicted != contract => Err(format!(
+            "`--contract` {contract} does not match the predicted address {predicted}"
+        )),
+        (Some(predicted), _) => Ok(predicted),
+        (None, Some(contract)) => Ok(contract.to_string()),
+        (None, None) => Err("either `--checksum` or `--contract` is required".to_string()),
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let path = args.required("--msg")?;
+    let msg: InstantiateMsg = from_json(read(path).map_err(|e| format!("{path}: {e}"))?)
+        .map_err(|e| format!("{path}: {e}"))?;
+    let code_id = args
+        .number("--code-id")?
+        .ok_or("`--code-id` is required")?;
+    let salt = args.required("--salt")?;
+    let contract = contract_address(args, salt)?;
+
+    validate(&msg)?;
+    if msg.owner != GOVERNANCE {
+        eprintln!(
+            "warning: the owner {} is not the governance module account",
+            msg.owner
+        );
+    }
+
+    let proposal = build_proposal(msg, code_id, salt, &contract, args)?;
+    println!("{}", to_json_string(&proposal).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Uint128;
+    use tc_vesting::payment::Schedule;
+
+    use super::*;
+
+    #[test]
+    fn test_proposal() {
+        let msg = InstantiateMsg {
+            owner: GOVERNANCE.to_string(),
+            recipient: "recv".to_string(),
+            title: "Payroll".to_string(),
+            description: None,
+            total: Uint128::new(1_000_000),
+            denom: UncheckedDenom::Native("uluna".to_string()),
+            schedule: Schedule::SaturatingLinear,
+            start_time: None,
+            vesting_duration_seconds: 100,
+            keeper: None,
+            cancel_notice_seconds: None,
+            guardian: None,
+            allow_ownership_transfer: None,
+            claim_deadline_seconds: None,
+        };
+        let args = Args::parse(["--deposit", "10uluna"].map(str::to_string).into_iter()).unwrap();
+        let proposal = build_proposal(msg, 42, "salt", "contract", &args).unwrap();
+        let json = to_json_string(&proposal).unwrap();
+
+        assert!(json.starts_with(
+            r#"{"messages":[{"@type":"/cosmwasm.wasm.v1.MsgInstantiateContract2","sender":"terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n","admin":"terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n","code_id":"42","label":"Payroll","msg":{"owner":"#
+        ));
+        assert!(json.contains(r#""funds":[],"salt":"c2FsdA==","fix_msg":false}"#));
+        assert!(json.contains(
+            r#"{"@type":"/cosmos.distribution.v1beta1.MsgCommunityPoolSpend","authority":"terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n","recipient":"contract","amount":[{"denom":"uluna","amount":"1000000"}]}"#
+        ));
+        assert!(json.contains(
+            r#"{"@type":"/cosmwasm.wasm.v1.MsgExecuteContract","sender":"terra10d07y265gmmuvt4z0w9aw880jnsr700juxf95n","contract":"contract","msg":{"fund":{}},"funds":[]}"#
+        ));
+        assert!(json.ends_with(
+            r#""metadata":"","deposit":"10uluna","title":"Vesting payment: Payroll","summary":"Instantiates a vesting contract at contract paying 1 LUNC to recv, funds it from the Community Pool and activates the vesting schedule."}"#
+        ));
+    }
+
+    #[test]
+    fn test_contract_address() {
+        let checksum = "13a1fc994cc6d1c81b746ee0c0ff6f90043875e0bf1d9be6b7d779fc978dc2a5";
+        // the prediction itself is checked against wasmd's vectors
+        let predicted = predict_bech32_address(
+            &HexBinary::from_hex(checksum).unwrap(),
+            GOVERNANCE,
+            b"payroll-2025",
+        )
+        .unwrap();
+        let predicted = predicted.as_str();
+        let args = |argv: &[&str]| Args::parse(argv.iter().map(|a| a.to_string())).unwrap();
+
+        assert_eq!(
+            contract_address(&args(&["--checksum", checksum]), "payroll-2025").unwrap(),
+            predicted
+        );
+        assert_eq!(
+            contract_address(
+                &args(&["--checksum", checksum, "--contract", predicted]),
+                "payroll-2025"
+            )
+            .unwrap(),
+            predicted
+        );
+        assert!(contract_address(
+            &args(&["--checksum", checksum, "--contract", predicted]),
+            "payroll-2026"
+        )
+        .is_err());
+        assert_eq!(
+            contract_address(&args(&["--contract", "contract"]), "salt").unwrap(),
+            "contract"
+        );
+        assert!(contract_address(&args(&[]), "salt").is_err());
+    }
+}
@@ -0,0 +1,399 @@
+//! Replays a scripted sequence of events against a payment, offline,
+//! through the same `Payment` logic the contract uses, and prints the
+//! amounts sent to the vestee and the Community Pool. Without a
+//! script, prints what both would receive if the payment was
+//! cancelled at each release.
+//!
+//! A script is a JSON list of steps, e.g.
+//!
+//! ```json
+//! [
+//!   { "at": "2025-02-01", "action": { "fund": {} } },
+//!   { "at": "2025-05-01", "action": { "distribute": {} } },
+//!   { "at": "2025-06-01", "action": { "pause": { "days": 14 } } },
+//!   { "at": "2025-07-01T12:00", "action": { "cancel": {} } }
+//! ]
+//! ```
+
+use std::fs::read;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::{from_json, BankMsg, CosmosMsg, DistributionMsg, Timestamp, Uint128};
+use tc_vesting::msg::InstantiateMsg;
+use tc_vesting::payment::{Status, Vest};
+use tc_vesting::state::PAYMENT;
+
+use crate::instantiate::{format_amount, parse_amount, unit, vest_init, DateTime, SECONDS_PER_DAY};
+use crate::Args;
+
+#[cw_serde]
+pub struct Step {
+    /// The time of the step in UTC, `YYYY-MM-DD[THH:MM[:SS]]`.
+    pub at: String,
+    pub action: Action,
+}
+
+#[cw_serde]
+pub enum Action {
+    /// The Community Pool sends AMOUNT whole tokens to the contract,
+    /// by default what has not been claimed yet, and `Fund` is called.
+    Fund {
+        amount: Option<String>,
+    },
+    /// The vestee claims AMOUNT whole tokens, by default everything
+    /// distributable.
+    Distribute {
+        amount: Option<String>,
+    },
+    /// The owner cancels the payment. With a notice period, the
+    /// cancellation is only scheduled.
+    Cancel {},
+    FinalizeCancel {},
+    /// Distributions are frozen for DAYS.
+    Pause {
+        days: u64,
+    },
+    Unpause {},
+}
+
+/// The outcome of a step.
+struct Row {
+    at: Timestamp,
+    action: String,
+    to_vestee: Uint128,
+    to_pool: Uint128,
+    note: String,
+}
+
+struct Simulation {
+    storage: MockStorage,
+    balance: Uint128,
+    to_vestee: Uint128,
+    to_pool: Uint128,
+}
+
+impl Simulation {
+    fn new(msg: &InstantiateMsg) -> Result<Self, String> {
+        if msg.start_time.is_none() {
+            return Err("simulations require a `start_time`".to_string());
+        }
+        let init = vest_init(msg, Timestamp::from_seconds(0))?;
+        let mut storage = MockStorage::new();
+        PAYMENT
+            .initialize(&mut storage, init)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            storage,
+            balance: Uint128::zero(),
+            to_vestee: Uint128::zero(),
+            to_pool: Uint128::zero(),
+        })
+    }
+
+    fn vest(&self) -> Vest {
+        PAYMENT.get_vest(&self.storage).expect("initialized")
+    }
+
+    /// Applies ACTION at T like the contract would and returns the
+    /// amounts sent to the vestee and the Community Pool. Failing
+    /// actions leave the payment untouched, except that funds sent by
+    /// the Community Pool stay in the contract.
+    fn apply(
+        &mut self,
+        t: Timestamp,
+        action: &Action,
+    ) -> Result<(Uint128, Uint128, String), String> {
+        let msgs = match action {
+            Action::Fund { amount } => {
+                let vest = self.vest();
+                let expected = vest.total() - vest.claimed;
+                self.balance += match amount {
+                    Some(amount) => parse_amount(amount)?,
+                    None => expected.saturating_sub(self.balance),
+                };
+                if !matches!(vest.status, Status::Unfunded | Status::AwaitingTopUp) {
+                    return Err(format!("can not fund a payment that is {:?}", vest.status));
+                }
+                if self.balance < expected {
+                    return Err(format!(
+                        "balance {} is short of {}",
+                        format_amount(self.balance),
+                        format_amount(expected)
+                    ));
+                }
+                PAYMENT
+                    .set_funded(&mut self.storage)
+                    .map_err(|e| e.to_string())?;
+                vec![]
+            }
+            Action::Distribute { amount } => {
+                let amount = amount.as_deref().map(parse_amount).transpose()?;
+                vec![PAYMENT
+                    .distribute(&mut self.storage, t, amount)
+                    .map_err(|e| e.to_string())?]
+            }
+            Action::Cancel {} if self.vest().cancel_notice_seconds > 0 => {
+                let effective = PAYMENT
+                    .schedule_cancel(&mut self.storage, t)
+                    .map_err(|e| e.to_string())?;
+                let effective = DateTime::from_unix(effective.seconds()).to_short_string();
+                return Ok((
+                    Uint128::zero(),
+                    Uint128::zero(),
+                    format!("effective {effective}"),
+                ));
+            }
+            Action::Cancel {} => PAYMENT
+                .cancel(&mut self.storage, t, self.balance)
+                .map_err(|e| e.to_string())?,
+            Action::FinalizeCancel {} => PAYMENT
+                .finalize_cancel(&mut self.storage, t, self.balance)
+                .map_err(|e| e.to_string())?,
+            Action::Pause { days } => {
+                PAYMENT
+                    .freeze(&mut self.storage, t, days * SECONDS_PER_DAY)
+                    .map_err(|e| e.to_string())?;
+                vec![]
+            }
+            Action::Unpause {} => {
+                PAYMENT
+                    .unfreeze(&mut self.storage)
+                    .map_err(|e| e.to_string())?;
+                vec![]
+            }
+        };
+
+        let recipient = self.vest().recipient;
+        let (mut to_vestee, mut to_pool) = (Uint128::zero(), Uint128::zero());
+        for msg in msgs {
+            match msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                    if to_address == recipient.as_str() =>
+                {
+                    to_vestee += amount.iter().map(|c| c.amount).sum::<Uint128>()
+                }
+                CosmosMsg::Distribution(DistributionMsg::FundCommunityPool { amount }) => {
+                    to_pool += amount.iter().map(|c| c.amount).sum::<Uint128>()
+                }
+                msg => return Err(format!("unexpected message {msg:?}")),
+            }
+        }
+        self.balance -= to_vestee + to_pool;
+        self.to_vestee += to_vestee;
+        self.to_pool += to_pool;
+
+        // vested tokens the balance did not cover on cancellation
+        let debt = self.vest().debt;
+        let note = if debt.is_zero() {
+            String::new()
+        } else {
+            format!("owes {} to the vestee", format_amount(debt))
+        };
+        Ok((to_vestee, to_pool, note))
+    }
+
+    fn step(&mut self, t: Timestamp, action: &Action) -> Row {
+        let (to_vestee, to_pool, note) = self
+            .apply(t, action)
+            .unwrap_or_else(|e| (Uint128::zero(), Uint128::zero(), format!("failed: {e}")));
+        Row {
+            at: t,
+            action: format!("{action:?}"),
+            to_vestee,
+            to_pool,
+            note,
+        }
+    }
+}
+
+/// Cancels a fresh copy of the payment, funded at its start, at each
+/// release and once before the first one.
+fn cancellation_table(msg: &InstantiateMsg) -> Result<Vec<Row>, String> {
+    let simulation = Simulation::new(msg)?;
+    let vest = simulation.vest();
+    let funded = vest.start_time();
+    let times = std::iter::once(funded).chain(vest.releases().into_iter().map(|r| r.time));
+
+    let mut rows = vec![];
+    for t in times {
+        let mut simulation = Simulation::new(msg)?;
+        simulation.step(funded, &Action::Fund { amount: None });
+        let mut row = simulation.step(t, &Action::Cancel {});
+        if vest.cancel_notice_seconds > 0 {
+            let effective = t.plus_seconds(vest.cancel_notice_seconds);
+            row = simulation.step(effective, &Action::FinalizeCancel {});
+            row.note = format!(
+                "effective {}",
+                DateTime::from_unix(effective.seconds()).to_short_string()
+            );
+            row.at = t;
+        }
+        row.action = "Cancel".to_string();
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn print_table(rows: &[Row], denom: &str) {
+    println!(
+        "{:<19}  {:<32}  {:>20}  {:>20}  note",
+        "time (UTC)",
+        "action",
+        format!("vestee ({})", unit(denom)),
+        format!("community pool ({})", unit(denom)),
+    );
+    for row in rows {
+        println!(
+            "{:<19}  {:<32}  {:>20}  {:>20}  {}",
+            DateTime::from_unix(row.at.seconds()).to_short_string(),
+            row.action,
+            format_amount(row.to_vestee),
+            format_amount(row.to_pool),
+            row.note,
+        );
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let path = args.required("--msg")?;
+    let msg: InstantiateMsg = from_json(read(path).map_err(|e| format!("{path}: {e}"))?)
+        .map_err(|e| format!("{path}: {e}"))?;
+    let denom = vest_init(&msg, Timestamp::from_seconds(0))?
+        .denom
+        .to_string();
+
+    let Some(path) = args.get("--script") else {
+        print_table(&cancellation_table(&msg)?, &denom);
+        return Ok(());
+    };
+    let steps: Vec<Step> = from_json(read(path).map_err(|e| format!("{path}: {e}"))?)
+        .map_err(|e| format!("{path}: {e}"))?;
+    let mut simulation = Simulation::new(&msg)?;
+    let mut rows = vec![];
+    for step in steps {
+        let at = Timestamp::from_seconds(DateTime::parse(&step.at)?.unix());
+        rows.push(simulation.step(at, &step.action));
+    }
+    print_table(&rows, &denom);
+    println!();
+    println!(
+        "total to vestee: {}, to community pool: {}, left in contract: {} {}",
+        format_amount(simulation.to_vestee),
+        format_amount(simulation.to_pool),
+        format_amount(simulation.balance),
+        unit(&denom),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tc_vesting::denom::UncheckedDenom;
+    use tc_vesting::payment::Schedule;
+
+    use super::*;
+
+    fn msg(cancel_notice_seconds: Option<u64>) -> InstantiateMsg {
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            recipient: "recv".to_string(),
+            title: "Payroll".to_string(),
+            description: None,
+            total: Uint128::new(100_000_000),
+            denom: UncheckedDenom::Native("uluna".to_string()),
+            schedule: Schedule::PiecewiseLinear(vec![
+                (1, Uint128::zero()),
+                (100, Uint128::zero()),
+                (101, Uint128::new(50_000_000)),
+                (200, Uint128::new(50_000_000)),
+                (201, Uint128::new(100_000_000)),
+            ]),
+            start_time: Some(Timestamp::from_seconds(1_000)),
+            vesting_duration_seconds: 201,
+            keeper: None,
+            cancel_notice_seconds,
+            guardian: None,
+            allow_ownership_transfer: None,
+            claim_deadline_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_script() {
+        let mut simulation = Simulation::new(&msg(None)).unwrap();
+        let t = Timestamp::from_seconds;
+
+        let fund = |amount: &str| Action::Fund {
+            amount: Some(amount.to_string()),
+        };
+        let row = simulation.step(t(1_000), &fund("60"));
+        assert!(row.note.starts_with("failed: balance 60 is short"));
+        let row = simulation.step(t(1_000), &fund("50"));
+        assert_eq!(row.note, "");
+
+        simulation.step(t(1_101), &Action::Pause { days: 1 });
+        let row = simulation.step(t(1_102), &Action::Distribute { amount: None });
+        assert!(row.note.starts_with("failed"));
+        simulation.step(t(1_103), &Action::Unpause {});
+        let row = simulation.step(t(1_103), &Action::Distribute { amount: None });
+        assert_eq!(row.to_vestee, Uint128::new(50_000_000));
+
+        let row = simulation.step(t(1_150), &Action::Cancel {});
+        assert_eq!(row.to_vestee, Uint128::zero());
+        assert_eq!(row.to_pool, Uint128::new(60_000_000));
+        assert_eq!(simulation.balance, Uint128::zero());
+    }
+
+    #[test]
+    fn test_partially_funded_cancel() {
+        let mut simulation = Simulation::new(&msg(None)).unwrap();
+        let t = Timestamp::from_seconds;
+
+        // the spend fell short, so the payment was never funded
+        let row = simulation.step(
+            t(1_000),
+            &Action::Fund {
+                amount: Some("30".to_string()),
+            },
+        );
+        assert!(row.note.starts_with("failed"));
+
+        let row = simulation.step(t(1_150), &Action::Cancel {});
+        assert_eq!(row.to_vestee, Uint128::new(30_000_000));
+        assert_eq!(row.to_pool, Uint128::zero());
+        assert_eq!(row.note, "owes 20 to the vestee");
+
+        // a later spend settles the debt
+        simulation.step(
+            t(1_200),
+            &Action::Fund {
+                amount: Some("20".to_string()),
+            },
+        );
+        let row = simulation.step(t(1_200), &Action::Distribute { amount: None });
+        assert_eq!(row.to_vestee, Uint128::new(20_000_000));
+        assert_eq!(row.note, "");
+        assert_eq!(simulation.balance, Uint128::zero());
+    }
+
+    #[test]
+    fn test_cancellation_table() {
+        let rows = cancellation_table(&msg(None)).unwrap();
+        assert_eq!(
+            rows.iter()
+                .map(|r| (r.at.seconds(), r.to_vestee.u128(), r.to_pool.u128()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1_000, 0, 100_000_000),
+                (1_101, 50_000_000, 50_000_000),
+                (1_201, 100_000_000, 0),
+            ]
+        );
+
+        // Vesting continues during the notice period.
+        let rows = cancellation_table(&msg(Some(101))).unwrap();
+        assert_eq!(rows[0].to_vestee, Uint128::new(50_000_000));
+        assert_eq!(rows[0].note, "effective 1970-01-01 00:18:21");
+    }
+}
@@ -2,18 +2,23 @@ use std::env;
 
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128,
+    to_json_binary, Binary, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Response,
+    StdResult, Timestamp, Uint128,
 };
 use cw2::set_contract_version;
-use cw_ownable::OwnershipError;
+use cw_ownable::{Action, OwnershipError};
 use cw_utils::nonpayable;
 
 use crate::denom::CheckedDenom;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::PAYMENT;
-use crate::payment::{Status, VestInit};
+use crate::msg::{ExecuteMsg, InstantiateMsg, InvariantsResponse, QueryMsg, SummaryResponse};
+use crate::events::{
+    record_transfer, record_transfers, CancelEvent, ExtendEvent, FreezeEvent, ReduceEvent,
+    RevokeCancelEvent, ScheduleCancelEvent, UnfreezeEvent,
+};
+use crate::history::HistoryKind;
+use crate::state::{HISTORY, OWNERSHIP_TRANSFERABLE, PAYMENT};
+use crate::payment::{IbcPayout, Schedule, Status, UncheckedClaimPolicy, VestInit};
 
 const CONTRACT_NAME: &str = "crates.io:tc-vesting";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -39,6 +44,7 @@ pub fn instantiate(
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(&msg.owner.as_str()))?;
+    OWNERSHIP_TRANSFERABLE.save(deps.storage, &msg.allow_ownership_transfer.unwrap_or(false))?;
 
     // ensure we are not trying to vest a cw20 token because
     // we can not send cw20 tokens to the community pool
@@ -49,6 +55,10 @@ pub fn instantiate(
     }?;
 
     let recipient = deps.api.addr_validate(&msg.recipient)?;
+    let guardian = msg
+        .guardian
+        .map(|g| deps.api.addr_validate(&g))
+        .transpose()?;
     let start_time = msg.start_time.unwrap_or(env.block.time);
 
     if start_time.plus_seconds(msg.vesting_duration_seconds) <= env.block.time {
@@ -66,6 +76,10 @@ pub fn instantiate(
             recipient,
             title: msg.title,
             description: msg.description,
+            keeper: msg.keeper,
+            cancel_notice_seconds: msg.cancel_notice_seconds.unwrap_or_default(),
+            guardian,
+            claim_deadline_seconds: msg.claim_deadline_seconds,
         },
     )?;
 
@@ -82,16 +96,59 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if PAYMENT.get_vest(deps.storage)?.status == Status::Closed {
+        return Err(ContractError::Closed);
+    }
+
     match msg {
         ExecuteMsg::Fund {} => execute_fund(env, deps, info),
         ExecuteMsg::Cancel {} => execute_cancel_vesting_payment(env, deps, info),
-        ExecuteMsg::Distribute { amount } => execute_distribute(env, deps, amount),
+        ExecuteMsg::FinalizeCancel {} => execute_finalize_cancel(env, deps, info),
+        ExecuteMsg::RevokeCancel {} => execute_revoke_cancel(env, deps, info),
+        ExecuteMsg::Reduce { new_total } => execute_reduce(env, deps, info, new_total),
+        ExecuteMsg::Extend {
+            additional_total,
+            additional_duration_seconds,
+            schedule,
+        } => execute_extend(env, deps, info, additional_total, additional_duration_seconds, schedule),
+        ExecuteMsg::Freeze { duration_seconds } => execute_freeze(env, deps, info, duration_seconds),
+        ExecuteMsg::Unfreeze {} => execute_unfreeze(env, deps, info),
+        ExecuteMsg::UpdateGuardian { guardian } => execute_update_guardian(deps, info, guardian),
+        ExecuteMsg::Distribute { amount } => execute_distribute(env, deps, info, amount),
+        ExecuteMsg::Close {} => execute_close(env, deps, info),
+        ExecuteMsg::ReclaimUnclaimed {} => execute_reclaim_unclaimed(env, deps, info),
+        ExecuteMsg::SetIbcPayout { payout } => execute_set_ibc_payout(deps, info, payout),
+        ExecuteMsg::ClaimIbcRefund {} => execute_claim_ibc_refund(env, deps, info),
+        ExecuteMsg::SetClaimPolicy { policy } => execute_set_claim_policy(deps, info, policy),
 
-        // we do not allow updating the ownership - this is a one-way trip
-        ExecuteMsg::UpdateOwnership(_msg) => Err(ContractError::Ownable(OwnershipError::NoOwner)),
+        ExecuteMsg::UpdateOwnership(action) => execute_update_ownership(env, deps, info, action),
     }
 }
 
+pub fn execute_update_ownership(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+    action: Action,
+) -> Result<Response, ContractError> {
+    // unless enabled at instantiation we do not allow updating the
+    // ownership - this is a one-way trip
+    if !OWNERSHIP_TRANSFERABLE.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::Ownable(OwnershipError::NoOwner));
+    }
+
+    // without an owner nobody could ever cancel the payment
+    if action == Action::RenounceOwnership {
+        return Err(ContractError::RenounceOwnership);
+    }
+
+    let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_ownership")
+        .add_attributes(ownership.into_attributes()))
+}
+
 pub fn execute_fund(
     env: Env,
     deps: DepsMut,
@@ -105,27 +162,39 @@ pub fn execute_fund(
 
     // 1.)  If the contract is already funded, we do nothing
     //      If the contract is canceled, we do nothing
-    //      If the contract is unfunded, we continue
+    //      If the contract is unfunded or awaits a top-up, we continue
     let vest = PAYMENT.get_vest(deps.storage)?;
     match vest.status {
-        Status::Unfunded => (),
+        Status::Unfunded | Status::AwaitingTopUp => (),
         Status::Funded => return Err(ContractError::Funded),
         Status::Canceled { .. } => return Err(ContractError::Cancelled),
+        Status::Completed | Status::Closed => return Err(ContractError::Completed),
     };
 
-    // 2.)  Check the token balance of the contract
+    // 2.)  Check the token balance of the contract covers everything
+    //      that has not been claimed yet
     let token = vest.clone().denom;
     let balance = token.query_balance(&deps.querier, &env.contract.address)?;
-    if balance < vest.total() {
-        return Err(ContractError::WrongFundAmount { sent: balance, expected: vest.total() });
+    let expected = vest.total() - vest.claimed;
+    if balance < expected {
+        return Err(ContractError::WrongFundAmount { sent: balance, expected });
     }
 
     // 3.) if balance is sufficient, we mark the contract as funded
+    //     and record what this funding added, which is only the
+    //     top-up for an extension
+    let added = if vest.status == Status::AwaitingTopUp {
+        vest.top_up
+    } else {
+        balance
+    };
     PAYMENT.set_funded(deps.storage)?;
+    let event = record_transfer(deps.storage, &env.block, HistoryKind::Fund, env.contract.address, added)?;
 
     Ok(Response::new()
         .add_attribute("method", "fund")
-        .add_attribute("from", info.sender))
+        .add_attribute("from", info.sender)
+        .add_event(event))
 }
 
 pub fn execute_cancel_vesting_payment(
@@ -134,28 +203,322 @@ pub fn execute_cancel_vesting_payment(
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
-    let total_balance = PAYMENT.get_vest(deps.storage)?.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let vest = PAYMENT.get_vest(deps.storage)?;
+
+    // with a notice period the cancellation is only scheduled
+    if vest.cancel_notice_seconds > 0 {
+        let effective = PAYMENT.schedule_cancel(deps.storage, env.block.time)?;
+        let event = ScheduleCancelEvent {
+            effective_time: effective,
+            height: env.block.height,
+            time: env.block.time,
+        };
+        return Ok(Response::new()
+            .add_attribute("method", "schedule_cancel")
+            .add_attribute("owner", info.sender)
+            .add_attribute("effective_time", effective.to_string())
+            .add_event(Event::from(event)));
+    }
+
+    let total_balance = vest.denom.query_balance(&deps.querier, &env.contract.address)?;
     let msgs = PAYMENT.cancel(deps.storage, env.block.time, total_balance)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::CancelPayout, &msgs)?;
+    let cancel = CancelEvent::new(env.block.time, &PAYMENT.get_vest(deps.storage)?, &env.block);
 
     Ok(Response::new()
         .add_attribute("method", "remove_vesting_payment")
         .add_attribute("owner", info.sender)
         .add_attribute("removed_time", env.block.time.to_string())
+        .add_event(Event::from(cancel))
+        .add_events(events)
+        .add_messages(msgs))
+}
+
+pub fn execute_finalize_cancel(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let total_balance = vest.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let msgs = PAYMENT.finalize_cancel(deps.storage, env.block.time, total_balance)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::CancelPayout, &msgs)?;
+    let cancel = CancelEvent::new(
+        vest.pending_cancel.unwrap_or(env.block.time),
+        &PAYMENT.get_vest(deps.storage)?,
+        &env.block,
+    );
+
+    Ok(Response::new()
+        .add_attribute("method", "finalize_cancel")
+        .add_attribute("from", info.sender)
+        .add_event(Event::from(cancel))
+        .add_events(events)
         .add_messages(msgs))
 }
 
+pub fn execute_close(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let total_balance = PAYMENT.get_vest(deps.storage)?.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let msgs = PAYMENT.close(deps.storage, env.block.time, total_balance)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::Clawback, &msgs)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "close")
+        .add_attribute("from", info.sender)
+        .add_events(events)
+        .add_messages(msgs))
+}
+
+pub fn execute_reclaim_unclaimed(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let total_balance = PAYMENT.get_vest(deps.storage)?.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let msgs = PAYMENT.reclaim_unclaimed(deps.storage, env.block.time, total_balance)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::Clawback, &msgs)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reclaim_unclaimed")
+        .add_attribute("from", info.sender)
+        .add_events(events)
+        .add_messages(msgs))
+}
+
+pub fn execute_revoke_cancel(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    PAYMENT.revoke_cancel(deps.storage)?;
+    let event = RevokeCancelEvent {
+        effective_time: vest.pending_cancel.unwrap_or(env.block.time),
+        height: env.block.height,
+        time: env.block.time,
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_cancel")
+        .add_attribute("owner", info.sender)
+        .add_event(Event::from(event)))
+}
+
+pub fn execute_reduce(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+    new_total: Uint128,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let previous_total = PAYMENT.get_vest(deps.storage)?.total();
+    let msg = PAYMENT.reduce(deps.storage, env.block.time, new_total)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::Clawback, std::slice::from_ref(&msg))?;
+    let reduce = ReduceEvent {
+        previous_total,
+        total: new_total,
+        height: env.block.height,
+        time: env.block.time,
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "reduce")
+        .add_attribute("owner", info.sender)
+        .add_attribute("new_total", new_total)
+        .add_event(Event::from(reduce))
+        .add_events(events)
+        .add_message(msg))
+}
+
+pub fn execute_extend(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+    additional_total: Uint128,
+    additional_duration_seconds: u64,
+    schedule: Schedule,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let vest = PAYMENT.extend(
+        deps.storage,
+        env.block.time,
+        additional_total,
+        additional_duration_seconds,
+        schedule,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "extend")
+        .add_attribute("owner", info.sender)
+        .add_attribute("additional_total", additional_total)
+        .add_attribute("new_total", vest.total())
+        .add_event(Event::from(ExtendEvent::new(additional_total, &vest, &env.block))))
+}
+
+pub fn execute_freeze(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+    duration_seconds: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // the owner may freeze at will, the guardian only within bounds
+    let until = if cw_ownable::assert_owner(deps.storage, &info.sender).is_ok() {
+        PAYMENT.freeze(deps.storage, env.block.time, duration_seconds)?
+    } else {
+        let vest = PAYMENT.get_vest(deps.storage)?;
+        if vest.guardian.as_ref() != Some(&info.sender) {
+            return Err(ContractError::NotGuardian);
+        }
+        PAYMENT.guardian_freeze(deps.storage, env.block.time, duration_seconds)?
+    };
+
+    let event = FreezeEvent {
+        by: info.sender.to_string(),
+        frozen_until: until,
+        height: env.block.height,
+        time: env.block.time,
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "freeze")
+        .add_attribute("from", info.sender)
+        .add_attribute("frozen_until", until.to_string())
+        .add_event(Event::from(event)))
+}
+
+pub fn execute_unfreeze(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    PAYMENT.unfreeze(deps.storage)?;
+    let event = UnfreezeEvent {
+        height: env.block.height,
+        time: env.block.time,
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "unfreeze")
+        .add_attribute("owner", info.sender)
+        .add_event(Event::from(event)))
+}
+
+pub fn execute_update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let guardian = guardian
+        .map(|g| deps.api.addr_validate(&g))
+        .transpose()?;
+    PAYMENT.set_guardian(deps.storage, guardian.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_guardian")
+        .add_attribute("owner", info.sender)
+        .add_attribute("guardian", guardian.map_or("none".to_string(), |g| g.to_string())))
+}
+
 pub fn execute_distribute(
     env: Env,
     deps: DepsMut,
+    info: MessageInfo,
     request: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let msg = PAYMENT.distribute(deps.storage, env.block.time, request)?;
+    // the recipient claims without tip and rate limit, anyone
+    // else is treated as a keeper
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if !vest.may_distribute(&info.sender) {
+        return Err(ContractError::NotReceiver);
+    }
+    let msgs = if info.sender == vest.recipient {
+        vec![PAYMENT.distribute(deps.storage, env.block.time, request)?]
+    } else {
+        PAYMENT.keeper_distribute(deps.storage, env.block.time, request, &info.sender)?
+    };
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::Distribute, &msgs)?;
 
     Ok(Response::new()
         .add_attribute("method", "distribute")
+        .add_attribute("sender", info.sender)
+        .add_events(events)
+        .add_messages(msgs))
+}
+
+pub fn execute_set_ibc_payout(
+    deps: DepsMut,
+    info: MessageInfo,
+    payout: Option<IbcPayout>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if info.sender != vest.recipient {
+        return Err(ContractError::NotReceiver);
+    }
+    PAYMENT.set_ibc_payout(deps.storage, payout.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_ibc_payout")
+        .add_attribute("channel_id", payout.as_ref().map_or("none", |p| p.channel_id.as_str()))
+        .add_attribute("remote_address", payout.as_ref().map_or("none", |p| p.remote_address.as_str())))
+}
+
+pub fn execute_claim_ibc_refund(
+    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if info.sender != vest.recipient {
+        return Err(ContractError::NotReceiver);
+    }
+    let total_balance = vest.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let msg = PAYMENT.claim_ibc_refund(deps.storage, total_balance)?;
+    let events = record_transfers(deps.storage, &env.block, HistoryKind::Refund, std::slice::from_ref(&msg))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "claim_ibc_refund")
+        .add_events(events)
         .add_message(msg))
 }
 
+pub fn execute_set_claim_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    policy: UncheckedClaimPolicy,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if info.sender != vest.recipient {
+        return Err(ContractError::NotReceiver);
+    }
+    let policy = policy.into_checked(deps.api)?;
+    PAYMENT.set_claim_policy(deps.storage, policy)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_claim_policy")
+        .add_attribute("recipient", info.sender))
+}
+
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -173,5 +536,76 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         ),
         QueryMsg::TotalToVest {} => to_json_binary(&PAYMENT.get_vest(deps.storage)?.total()),
         QueryMsg::VestDuration {} => to_json_binary(&PAYMENT.duration(deps.storage)?),
+        QueryMsg::Operators {} => to_json_binary(&PAYMENT.get_vest(deps.storage)?.operators()),
+        QueryMsg::History {
+            start_after,
+            limit,
+            kind,
+        } => to_json_binary(&HISTORY.list(deps.storage, start_after, limit, kind)?),
+        QueryMsg::Timeline { from, to, points } => {
+            to_json_binary(&PAYMENT.get_vest(deps.storage)?.timeline(from, to, points)?)
+        }
+        QueryMsg::Breakpoints {} => to_json_binary(&PAYMENT.get_vest(deps.storage)?.breakpoints()),
+        QueryMsg::Summary { t } => to_json_binary(&query_summary(deps, env, t)?),
+        QueryMsg::NextRelease { after } => to_json_binary(
+            &PAYMENT
+                .get_vest(deps.storage)?
+                .next_release(after.unwrap_or(env.block.time)),
+        ),
+        QueryMsg::ReleaseCalendar { start_after, limit } => to_json_binary(
+            &PAYMENT
+                .get_vest(deps.storage)?
+                .release_calendar(start_after, limit),
+        ),
+        QueryMsg::Invariants {} => to_json_binary(&query_invariants(deps, env)?),
+        QueryMsg::ClaimDeadline {} => to_json_binary(&PAYMENT.get_vest(deps.storage)?.claim_deadline()),
     }
 }
+
+pub fn query_invariants(deps: Deps, env: Env) -> StdResult<InvariantsResponse> {
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let balance = vest.denom.query_balance(&deps.querier, &env.contract.address)?;
+    let violations = vest.invariant_violations(env.block.time, balance);
+
+    Ok(InvariantsResponse {
+        ok: violations.is_empty(),
+        violations,
+    })
+}
+
+pub fn query_summary(deps: Deps, env: Env, t: Option<Timestamp>) -> StdResult<SummaryResponse> {
+    let t = t.unwrap_or(env.block.time);
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let balance = vest.denom.query_balance(&deps.querier, &env.contract.address)?;
+
+    let total = vest.total();
+    let vested = vest.vested(t);
+    // the tokens the contract has to hold for the vestee
+    let owed = match vest.status {
+        Status::Funded => total - vest.claimed,
+        Status::AwaitingTopUp => (total - vest.top_up).saturating_sub(vest.claimed),
+        Status::Canceled => vest.debt,
+        Status::Unfunded | Status::Completed | Status::Closed => Uint128::zero(),
+    };
+    let percent_complete = if total.is_zero() {
+        Decimal::one()
+    } else {
+        Decimal::from_ratio(vested, total)
+    };
+
+    Ok(SummaryResponse {
+        status: vest.status.clone(),
+        total,
+        vested,
+        claimed: vest.claimed,
+        distributable: PAYMENT.distributable(deps.storage, &vest, t)?,
+        balance,
+        surplus: balance.saturating_sub(owed),
+        shortfall: owed.saturating_sub(balance),
+        start_time: vest.start_time(),
+        end_time: vest.end_time(),
+        percent_complete,
+        next_release: vest.next_release(t).map(|r| r.time),
+        claim_deadline: vest.claim_deadline(),
+    })
+}
@@ -0,0 +1,357 @@
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{coins, from_json, Addr, BankMsg, CosmosMsg, Env, OwnedDeps, Response, Uint128};
+use cw_ownable::{get_ownership, Action, Expiration, OwnershipError};
+
+use crate::contract::{execute, instantiate, query};
+use crate::denom::UncheckedDenom;
+use crate::error::ContractError;
+use crate::history::HistoryEntry;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SummaryResponse};
+use crate::payment::{IbcPayout, Schedule, GUARDIAN_FREEZE_COOLDOWN_SECONDS};
+
+const OWNER: &str = "owner";
+const RECIPIENT: &str = "recipient";
+const GUARDIAN: &str = "guardian";
+const DENOM: &str = "uluna";
+const DAY: u64 = 24 * 60 * 60;
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: OWNER.to_string(),
+        recipient: RECIPIENT.to_string(),
+        title: "title".to_string(),
+        description: None,
+        total: Uint128::new(100_000_000),
+        denom: UncheckedDenom::Native(DENOM.to_string()),
+        schedule: Schedule::SaturatingLinear,
+        start_time: None,
+        vesting_duration_seconds: 100 * DAY,
+        keeper: None,
+        cancel_notice_seconds: None,
+        guardian: Some(GUARDIAN.to_string()),
+        allow_ownership_transfer: None,
+        claim_deadline_seconds: None,
+    }
+}
+
+/// Instantiates the contract with MSG and funds it.
+fn setup(msg: InstantiateMsg) -> (MockDeps, Env) {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let total = msg.total.u128();
+    instantiate(deps.as_mut(), env.clone(), mock_info(OWNER, &[]), msg).unwrap();
+    deps.querier.update_balance(&env.contract.address, coins(total, DENOM));
+    exec(&mut deps, &env, OWNER, ExecuteMsg::Fund {}).unwrap();
+    (deps, env)
+}
+
+fn exec(
+    deps: &mut MockDeps,
+    env: &Env,
+    sender: &str,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), msg)
+}
+
+fn query_summary(deps: &MockDeps, env: &Env) -> SummaryResponse {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::Summary { t: None }).unwrap()).unwrap()
+}
+
+#[test]
+fn test_guardian_freeze_cooldown() {
+    let (mut deps, mut env) = setup(instantiate_msg());
+
+    let freeze = ExecuteMsg::Freeze {
+        duration_seconds: DAY,
+    };
+    exec(&mut deps, &env, GUARDIAN, freeze.clone()).unwrap();
+    let until = env.block.time.plus_seconds(DAY);
+
+    // the guardian can not freeze again once its freeze expires
+    env.block.time = until;
+    let distribute = ExecuteMsg::Distribute { amount: None };
+    exec(&mut deps, &env, RECIPIENT, distribute.clone()).unwrap();
+    let cooldown = until.plus_seconds(GUARDIAN_FREEZE_COOLDOWN_SECONDS);
+    assert_eq!(
+        exec(&mut deps, &env, GUARDIAN, freeze.clone()).unwrap_err(),
+        ContractError::GuardianCooldown { until: cooldown }
+    );
+
+    // governance reappointing the guardian resets the cooldown
+    let reappoint = ExecuteMsg::UpdateGuardian {
+        guardian: Some(GUARDIAN.to_string()),
+    };
+    exec(&mut deps, &env, OWNER, reappoint).unwrap();
+    exec(&mut deps, &env, GUARDIAN, freeze.clone()).unwrap();
+    assert_eq!(
+        exec(&mut deps, &env, RECIPIENT, distribute).unwrap_err(),
+        ContractError::Frozen {
+            until: env.block.time.plus_seconds(DAY)
+        }
+    );
+
+    // as does waiting for the cooldown to pass
+    env.block.time = env
+        .block
+        .time
+        .plus_seconds(DAY + GUARDIAN_FREEZE_COOLDOWN_SECONDS - 1);
+    assert!(matches!(
+        exec(&mut deps, &env, GUARDIAN, freeze.clone()).unwrap_err(),
+        ContractError::GuardianCooldown { .. }
+    ));
+    env.block.time = env.block.time.plus_seconds(1);
+    exec(&mut deps, &env, GUARDIAN, freeze).unwrap();
+}
+
+#[test]
+fn test_schedule_events() {
+    let (mut deps, env) = setup(instantiate_msg());
+
+    let event = |res: &Response, ty: &str, key: &str| {
+        res.events
+            .iter()
+            .find(|e| e.ty == format!("tc_vesting_{ty}"))
+            .and_then(|e| e.attributes.iter().find(|a| a.key == key))
+            .map(|a| a.value.clone())
+            .unwrap_or_else(|| panic!("no {key} in {ty} event"))
+    };
+
+    let reduce = ExecuteMsg::Reduce {
+        new_total: Uint128::new(80_000_000),
+    };
+    let res = exec(&mut deps, &env, OWNER, reduce).unwrap();
+    assert_eq!(event(&res, "reduce", "previous_total"), "100000000");
+    assert_eq!(event(&res, "reduce", "total"), "80000000");
+    assert_eq!(event(&res, "clawback", "amount"), "20000000");
+
+    let extend = ExecuteMsg::Extend {
+        additional_total: Uint128::new(20_000_000),
+        additional_duration_seconds: 50 * DAY,
+        schedule: Schedule::SaturatingLinear,
+    };
+    let res = exec(&mut deps, &env, OWNER, extend).unwrap();
+    assert_eq!(event(&res, "extend", "total"), "100000000");
+    assert_eq!(
+        event(&res, "extend", "end_time"),
+        env.block.time.plus_seconds(150 * DAY).to_string()
+    );
+
+    let freeze = ExecuteMsg::Freeze {
+        duration_seconds: DAY,
+    };
+    let res = exec(&mut deps, &env, OWNER, freeze).unwrap();
+    assert_eq!(event(&res, "freeze", "by"), OWNER);
+    assert_eq!(
+        event(&res, "freeze", "frozen_until"),
+        env.block.time.plus_seconds(DAY).to_string()
+    );
+    let res = exec(&mut deps, &env, OWNER, ExecuteMsg::Unfreeze {}).unwrap();
+    assert_eq!(event(&res, "unfreeze", "time"), env.block.time.to_string());
+}
+
+#[test]
+fn test_fund_top_up_history() {
+    let (mut deps, env) = setup(instantiate_msg());
+
+    let extend = ExecuteMsg::Extend {
+        additional_total: Uint128::new(20_000_000),
+        additional_duration_seconds: 50 * DAY,
+        schedule: Schedule::SaturatingLinear,
+    };
+    exec(&mut deps, &env, OWNER, extend).unwrap();
+    deps.querier.update_balance(&env.contract.address, coins(120_000_000, DENOM));
+    exec(&mut deps, &env, OWNER, ExecuteMsg::Fund {}).unwrap();
+
+    // only the top-up is recorded for the second funding
+    let history: Vec<HistoryEntry> = from_json(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::History {
+                start_after: None,
+                limit: None,
+                kind: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let funded: Vec<_> = history.iter().map(|e| e.amount.u128()).collect();
+    assert_eq!(funded, vec![100_000_000, 20_000_000]);
+}
+
+#[test]
+fn test_claim_ibc_refund() {
+    let (mut deps, mut env) = setup(instantiate_msg());
+
+    let payout = ExecuteMsg::SetIbcPayout {
+        payout: Some(IbcPayout {
+            channel_id: "channel-1".to_string(),
+            remote_address: "osmo1recv".to_string(),
+            timeout_seconds: 600,
+        }),
+    };
+    exec(&mut deps, &env, RECIPIENT, payout).unwrap();
+    env.block.time = env.block.time.plus_seconds(50 * DAY);
+    exec(&mut deps, &env, RECIPIENT, ExecuteMsg::Distribute { amount: None }).unwrap();
+
+    // the transfer timed out and its tokens are back in the contract
+    deps.querier.update_balance(&env.contract.address, coins(100_000_000, DENOM));
+    assert_eq!(
+        exec(&mut deps, &env, OWNER, ExecuteMsg::ClaimIbcRefund {}).unwrap_err(),
+        ContractError::NotReceiver
+    );
+    let res = exec(&mut deps, &env, RECIPIENT, ExecuteMsg::ClaimIbcRefund {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: RECIPIENT.to_string(),
+            amount: coins(50_000_000, DENOM),
+        })
+    );
+    let refund = res
+        .events
+        .iter()
+        .find(|e| e.ty == "tc_vesting_refund")
+        .unwrap();
+    assert!(refund
+        .attributes
+        .iter()
+        .any(|a| a.key == "amount" && a.value == "50000000"));
+}
+
+#[test]
+fn test_ownership_rejection() {
+    let (mut deps, env) = setup(instantiate_msg());
+
+    let transfer = Action::TransferOwnership {
+        new_owner: "new_owner".to_string(),
+        expiry: None,
+    };
+    assert_eq!(
+        exec(&mut deps, &env, OWNER, ExecuteMsg::UpdateOwnership(transfer)).unwrap_err(),
+        ContractError::Ownable(OwnershipError::NoOwner)
+    );
+    assert_eq!(
+        get_ownership(&deps.storage).unwrap().owner,
+        Some(Addr::unchecked(OWNER))
+    );
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let (mut deps, env) = setup(InstantiateMsg {
+        allow_ownership_transfer: Some(true),
+        ..instantiate_msg()
+    });
+
+    let transfer = ExecuteMsg::UpdateOwnership(Action::TransferOwnership {
+        new_owner: "new_owner".to_string(),
+        expiry: None,
+    });
+    assert_eq!(
+        exec(&mut deps, &env, RECIPIENT, transfer.clone()).unwrap_err(),
+        ContractError::Ownable(OwnershipError::NotOwner)
+    );
+    let renounce = ExecuteMsg::UpdateOwnership(Action::RenounceOwnership);
+    assert_eq!(
+        exec(&mut deps, &env, OWNER, renounce).unwrap_err(),
+        ContractError::RenounceOwnership
+    );
+
+    // the transfer is pending until accepted
+    exec(&mut deps, &env, OWNER, transfer).unwrap();
+    assert_eq!(
+        get_ownership(&deps.storage).unwrap().owner,
+        Some(Addr::unchecked(OWNER))
+    );
+    let accept = ExecuteMsg::UpdateOwnership(Action::AcceptOwnership);
+    exec(&mut deps, &env, "new_owner", accept).unwrap();
+    assert_eq!(
+        get_ownership(&deps.storage).unwrap().owner,
+        Some(Addr::unchecked("new_owner"))
+    );
+
+    assert_eq!(
+        exec(&mut deps, &env, OWNER, ExecuteMsg::Cancel {}).unwrap_err(),
+        ContractError::Ownable(OwnershipError::NotOwner)
+    );
+    exec(&mut deps, &env, "new_owner", ExecuteMsg::Cancel {}).unwrap();
+}
+
+#[test]
+fn test_ownership_transfer_expiry() {
+    let (mut deps, mut env) = setup(InstantiateMsg {
+        allow_ownership_transfer: Some(true),
+        ..instantiate_msg()
+    });
+
+    let transfer = |env: &Env| {
+        ExecuteMsg::UpdateOwnership(Action::TransferOwnership {
+            new_owner: "new_owner".to_string(),
+            expiry: Some(Expiration::AtTime(env.block.time.plus_seconds(DAY))),
+        })
+    };
+    let accept = ExecuteMsg::UpdateOwnership(Action::AcceptOwnership);
+    exec(&mut deps, &env, OWNER, transfer(&env)).unwrap();
+    assert_eq!(
+        exec(&mut deps, &env, RECIPIENT, accept.clone()).unwrap_err(),
+        ContractError::Ownable(OwnershipError::NotPendingOwner)
+    );
+
+    // the pending owner can no longer accept once the transfer expired
+    env.block.time = env.block.time.plus_seconds(DAY);
+    assert_eq!(
+        exec(&mut deps, &env, "new_owner", accept.clone()).unwrap_err(),
+        ContractError::Ownable(OwnershipError::TransferExpired)
+    );
+    assert_eq!(
+        get_ownership(&deps.storage).unwrap().owner,
+        Some(Addr::unchecked(OWNER))
+    );
+
+    // the owner may offer the transfer again
+    exec(&mut deps, &env, OWNER, transfer(&env)).unwrap();
+    env.block.time = env.block.time.plus_seconds(DAY - 1);
+    exec(&mut deps, &env, "new_owner", accept).unwrap();
+    assert_eq!(
+        get_ownership(&deps.storage).unwrap().owner,
+        Some(Addr::unchecked("new_owner"))
+    );
+}
+
+#[test]
+fn test_summary_balance() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let contract = env.contract.address.clone();
+    instantiate(deps.as_mut(), env.clone(), mock_info(OWNER, &[]), instantiate_msg()).unwrap();
+
+    // nothing is owed before funding
+    deps.querier.update_balance(&contract, coins(30_000_000, DENOM));
+    let summary = query_summary(&deps, &env);
+    assert_eq!(summary.surplus, Uint128::new(30_000_000));
+    assert_eq!(summary.shortfall, Uint128::zero());
+
+    deps.querier.update_balance(&contract, coins(105_000_000, DENOM));
+    exec(&mut deps, &env, OWNER, ExecuteMsg::Fund {}).unwrap();
+    env.block.time = env.block.time.plus_seconds(40 * DAY);
+    exec(&mut deps, &env, RECIPIENT, ExecuteMsg::Distribute { amount: None }).unwrap();
+    deps.querier.update_balance(&contract, coins(65_000_000, DENOM));
+    let summary = query_summary(&deps, &env);
+    assert_eq!(summary.surplus, Uint128::new(5_000_000));
+    assert_eq!(summary.shortfall, Uint128::zero());
+
+    // a settled cancellation owes nothing and ends vesting
+    exec(&mut deps, &env, OWNER, ExecuteMsg::Cancel {}).unwrap();
+    deps.querier.update_balance(&contract, coins(0, DENOM));
+    let summary = query_summary(&deps, &env);
+    assert_eq!(summary.surplus, Uint128::zero());
+    assert_eq!(summary.shortfall, Uint128::zero());
+    assert_eq!(summary.end_time, env.block.time);
+}
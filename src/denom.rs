@@ -2,7 +2,7 @@ use std::fmt::{self};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, CustomQuery, Deps, DistributionMsg, QuerierWrapper, StdError, StdResult, Uint128, WasmMsg
+    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, CustomQuery, Deps, DistributionMsg, IbcMsg, IbcTimeout, QuerierWrapper, StdError, StdResult, Uint128, WasmMsg
 };
 
 use thiserror::Error;
@@ -169,6 +169,33 @@ impl CheckedDenom {
             CheckedDenom::Cw20(_address) => Err(StdError::generic_err("CW20 token cannot fund CP"))
         }  
     }
+
+    /// Gets a `CosmosMsg` that, when executed, will send AMOUNT tokens
+    /// over the ICS20 channel CHANNEL_ID to TO_ADDRESS on the remote
+    /// chain. Only native tokens can be transferred this way.
+    pub fn get_ibc_transfer_message(
+        &self,
+        channel_id: &str,
+        to_address: &str,
+        amount: Uint128,
+        timeout: IbcTimeout,
+    ) -> StdResult<CosmosMsg> {
+        match self {
+            CheckedDenom::Native(denom) => {
+                let msg = IbcMsg::Transfer {
+                    channel_id: channel_id.to_string(),
+                    to_address: to_address.to_string(),
+                    amount: Coin {
+                        denom: denom.to_string(),
+                        amount,
+                    },
+                    timeout,
+                };
+                Ok(msg.into())
+            },
+            CheckedDenom::Cw20(_address) => Err(StdError::generic_err("CW20 token cannot be sent via ICS20"))
+        }
+    }
 }
 
 /// Follows cosmos SDK validation logic. Specifically, the regex
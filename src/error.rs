@@ -1,4 +1,4 @@
-use cosmwasm_std::{StdError, Uint128};
+use cosmwasm_std::{OverflowError, StdError, Timestamp, Uint128};
 use crate::denom::DenomError;
 use cw_ownable::OwnershipError;
 use cw_utils::PaymentError;
@@ -10,6 +10,9 @@ pub enum ContractError {
     #[error(transparent)]
     Std(#[from] StdError),
 
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+
     #[error(transparent)]
     Curve(#[from] CurveError),
 
@@ -81,4 +84,67 @@ pub enum ContractError {
 
     #[error("can't register a slash event occuring in the future")]
     FutureSlash,
+
+    #[error("ibc payout requires a `channel-` id, a remote address and a non-zero timeout")]
+    InvalidIbcPayout,
+
+    #[error("ibc distributions may be refunded until ({until})")]
+    IbcRefundPending { until: Timestamp },
+
+    #[error("no refunded ibc distribution to claim")]
+    NoIbcRefund,
+
+    #[error("keeper tip rate may not exceed 10%")]
+    KeeperTipTooHigh,
+
+    #[error("keeper distributions are rate limited, next allowed at ({next})")]
+    KeeperTooEarly { next: Timestamp },
+
+    #[error("payment cancellation is already scheduled for ({effective})")]
+    CancelPending { effective: Timestamp },
+
+    #[error("no payment cancellation is scheduled")]
+    NoPendingCancel,
+
+    #[error("cancellation notice period ends at ({effective})")]
+    CancelNoticeActive { effective: Timestamp },
+
+    #[error("new total must be in [{min}, {max})")]
+    InvalidReduction { min: Uint128, max: Uint128 },
+
+    #[error("vesting contract is awaiting funding of an extension")]
+    AwaitingTopUp,
+
+    #[error("distributions are frozen until ({until})")]
+    Frozen { until: Timestamp },
+
+    #[error("only the owner or the guardian may freeze distributions")]
+    NotGuardian,
+
+    #[error("every token has been claimed")]
+    Completed,
+
+    #[error("payment is neither completed nor cancelled")]
+    NotCompleted,
+
+    #[error("the vest receiver is still owed ({debt}) tokens")]
+    Debt { debt: Uint128 },
+
+    #[error("vesting contract is closed")]
+    Closed,
+
+    #[error("payment has no claim deadline")]
+    NoClaimDeadline,
+
+    #[error("unclaimed tokens can be reclaimed after ({deadline})")]
+    ClaimDeadlineNotReached { deadline: Timestamp },
+
+    #[error("the owner may not renounce ownership")]
+    RenounceOwnership,
+
+    #[error("the guardian may freeze distributions for at most ({max}) seconds and not extend an active freeze")]
+    GuardianFreezeLimit { max: u64 },
+
+    #[error("the guardian may not freeze distributions again before ({until})")]
+    GuardianCooldown { until: Timestamp },
 }
@@ -0,0 +1,304 @@
+//! Typed events for indexers. Every token transfer into or out of
+//! the contract emits a `TransferEvent`, every cancellation taking
+//! effect emits a `CancelEvent` and every other change of the
+//! schedule or of a freeze emits an event of its own, so that the
+//! state of the payment can be reconstructed from events alone. The wasm module prefixes the
+//! event types with `wasm-`, e.g. `wasm-tc_vesting_distribute`.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{BlockInfo, CosmosMsg, Event, StdResult, Storage, Timestamp, Uint128};
+
+use crate::history::{HistoryEntry, HistoryKind};
+use crate::payment::Vest;
+use crate::state::{HISTORY, PAYMENT};
+
+pub const EVENT_PREFIX: &str = "tc_vesting";
+
+/// Emitted as `tc_vesting_<kind>` for each entry of the payment
+/// history, e.g. `tc_vesting_fund` or `tc_vesting_distribute`.
+#[cw_serde]
+pub struct TransferEvent {
+    pub kind: HistoryKind,
+    /// The sequence number of the history entry.
+    pub seq: u64,
+    pub amount: Uint128,
+    pub denom: String,
+    pub recipient: String,
+    /// The amount claimed by the vest receiver after the transfer.
+    pub claimed: Uint128,
+    /// The amount vested at block time.
+    pub vested: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_cancel` when a cancellation takes effect.
+#[cw_serde]
+pub struct CancelEvent {
+    /// The time vesting stopped.
+    pub effective_time: Timestamp,
+    /// The total amount that will ever vest after cancellation.
+    pub total: Uint128,
+    pub claimed: Uint128,
+    /// Vested tokens the balance did not cover, see `Vest::debt`.
+    pub debt: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_schedule_cancel` when the owner cancels a
+/// payment with a notice period.
+#[cw_serde]
+pub struct ScheduleCancelEvent {
+    /// The time the cancellation takes effect.
+    pub effective_time: Timestamp,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_revoke_cancel` when the owner revokes a
+/// scheduled cancellation.
+#[cw_serde]
+pub struct RevokeCancelEvent {
+    /// The time the revoked cancellation would have taken effect.
+    pub effective_time: Timestamp,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_reduce` when the owner reduces the total.
+#[cw_serde]
+pub struct ReduceEvent {
+    pub previous_total: Uint128,
+    /// The total amount that will ever vest after the reduction.
+    pub total: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_extend` when the owner extends the payment.
+#[cw_serde]
+pub struct ExtendEvent {
+    pub additional_total: Uint128,
+    /// The total amount that will ever vest after the extension.
+    pub total: Uint128,
+    /// The time the extended schedule ends.
+    pub end_time: Timestamp,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_freeze` when the owner or the guardian
+/// freezes distributions.
+#[cw_serde]
+pub struct FreezeEvent {
+    /// The owner or the guardian.
+    pub by: String,
+    pub frozen_until: Timestamp,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Emitted as `tc_vesting_unfreeze` when the owner lifts a freeze.
+#[cw_serde]
+pub struct UnfreezeEvent {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+impl TransferEvent {
+    pub fn new(entry: HistoryEntry, vest: &Vest) -> Self {
+        Self {
+            vested: vest.vested(entry.time),
+            kind: entry.kind,
+            seq: entry.seq,
+            amount: entry.amount,
+            denom: vest.denom.to_string(),
+            recipient: entry.to,
+            claimed: vest.claimed,
+            height: entry.height,
+            time: entry.time,
+        }
+    }
+}
+
+impl From<TransferEvent> for Event {
+    fn from(e: TransferEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_{}", e.kind.as_str()))
+            .add_attribute("seq", e.seq.to_string())
+            .add_attribute("amount", e.amount)
+            .add_attribute("denom", e.denom)
+            .add_attribute("recipient", e.recipient)
+            .add_attribute("claimed", e.claimed)
+            .add_attribute("vested", e.vested)
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl CancelEvent {
+    pub fn new(effective_time: Timestamp, vest: &Vest, block: &BlockInfo) -> Self {
+        Self {
+            effective_time,
+            total: vest.total(),
+            claimed: vest.claimed,
+            debt: vest.debt,
+            height: block.height,
+            time: block.time,
+        }
+    }
+}
+
+impl From<CancelEvent> for Event {
+    fn from(e: CancelEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_cancel"))
+            .add_attribute("effective_time", e.effective_time.to_string())
+            .add_attribute("total", e.total)
+            .add_attribute("claimed", e.claimed)
+            .add_attribute("debt", e.debt)
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl From<ScheduleCancelEvent> for Event {
+    fn from(e: ScheduleCancelEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_schedule_cancel"))
+            .add_attribute("effective_time", e.effective_time.to_string())
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl From<RevokeCancelEvent> for Event {
+    fn from(e: RevokeCancelEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_revoke_cancel"))
+            .add_attribute("effective_time", e.effective_time.to_string())
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl From<ReduceEvent> for Event {
+    fn from(e: ReduceEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_reduce"))
+            .add_attribute("previous_total", e.previous_total)
+            .add_attribute("total", e.total)
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl ExtendEvent {
+    pub fn new(additional_total: Uint128, vest: &Vest, block: &BlockInfo) -> Self {
+        Self {
+            additional_total,
+            total: vest.total(),
+            end_time: vest.end_time(),
+            height: block.height,
+            time: block.time,
+        }
+    }
+}
+
+impl From<ExtendEvent> for Event {
+    fn from(e: ExtendEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_extend"))
+            .add_attribute("additional_total", e.additional_total)
+            .add_attribute("total", e.total)
+            .add_attribute("end_time", e.end_time.to_string())
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl From<FreezeEvent> for Event {
+    fn from(e: FreezeEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_freeze"))
+            .add_attribute("by", e.by)
+            .add_attribute("frozen_until", e.frozen_until.to_string())
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+impl From<UnfreezeEvent> for Event {
+    fn from(e: UnfreezeEvent) -> Self {
+        Event::new(format!("{EVENT_PREFIX}_unfreeze"))
+            .add_attribute("height", e.height.to_string())
+            .add_attribute("time", e.time.to_string())
+    }
+}
+
+/// Records the transfers in MSGS in the payment history and returns
+/// an event for each of them.
+pub fn record_transfers(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    kind: HistoryKind,
+    msgs: &[CosmosMsg],
+) -> StdResult<Vec<Event>> {
+    let entries = HISTORY.record_msgs(storage, block, kind, msgs)?;
+    let vest = PAYMENT.get_vest(storage)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| TransferEvent::new(entry, &vest).into())
+        .collect())
+}
+
+/// Records a single transfer in the payment history and returns its
+/// event.
+pub fn record_transfer(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    kind: HistoryKind,
+    to: impl Into<String>,
+    amount: Uint128,
+) -> StdResult<Event> {
+    let entry = HISTORY.record(storage, block, kind, to, amount)?;
+    let vest = PAYMENT.get_vest(storage)?;
+    Ok(TransferEvent::new(entry, &vest).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_env;
+
+    use super::*;
+
+    #[test]
+    fn test_transfer_event() {
+        let block = mock_env().block;
+        let event: Event = TransferEvent {
+            kind: HistoryKind::CancelPayout,
+            seq: 3,
+            amount: Uint128::new(40),
+            denom: "uluna".to_string(),
+            recipient: "recv".to_string(),
+            claimed: Uint128::new(40),
+            vested: Uint128::new(40),
+            height: block.height,
+            time: block.time,
+        }
+        .into();
+
+        assert_eq!(event.ty, "tc_vesting_cancel_payout");
+        assert_eq!(
+            event
+                .attributes
+                .iter()
+                .map(|a| (a.key.as_str(), a.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("seq", "3"),
+                ("amount", "40"),
+                ("denom", "uluna"),
+                ("recipient", "recv"),
+                ("claimed", "40"),
+                ("vested", "40"),
+                ("height", block.height.to_string().as_str()),
+                ("time", block.time.to_string().as_str()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    BankMsg, BlockInfo, CosmosMsg, DistributionMsg, IbcMsg, Order, StdResult, Storage,
+    Timestamp, Uint128,
+};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, MultiIndex};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// The recipient recorded for payments to the Community Pool.
+pub const COMMUNITY_POOL: &str = "community_pool";
+
+/// A ledger of all token movements in and out of the contract,
+/// keyed by a sequence number.
+pub struct History<'a> {
+    entries: IndexedMap<'a, u64, HistoryEntry, HistoryIndexes<'a>>,
+    count: Item<'a, u64>,
+}
+
+#[cw_serde]
+pub enum HistoryKind {
+    /// The contract was marked as funded.
+    Fund,
+    /// Vested tokens were distributed, including keeper tips.
+    Distribute,
+    /// Vested tokens were paid to the vestee on cancellation.
+    CancelPayout,
+    /// Tokens were returned to the Community Pool.
+    Clawback,
+    /// Refunded IBC distributions were paid to the vestee locally.
+    Refund,
+}
+
+#[cw_serde]
+pub struct HistoryEntry {
+    pub seq: u64,
+    pub kind: HistoryKind,
+    pub amount: Uint128,
+    /// The receiver of the tokens.
+    pub to: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+pub struct HistoryIndexes<'a> {
+    pub kind: MultiIndex<'a, String, HistoryEntry, u64>,
+}
+
+impl<'a> IndexList<HistoryEntry> for HistoryIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<HistoryEntry>> + '_> {
+        let v: Vec<&dyn Index<HistoryEntry>> = vec![&self.kind];
+        Box::new(v.into_iter())
+    }
+}
+
+fn kind_index(_pk: &[u8], entry: &HistoryEntry) -> String {
+    entry.kind.as_str().to_string()
+}
+
+impl HistoryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryKind::Fund => "fund",
+            HistoryKind::Distribute => "distribute",
+            HistoryKind::CancelPayout => "cancel_payout",
+            HistoryKind::Clawback => "clawback",
+            HistoryKind::Refund => "refund",
+        }
+    }
+}
+
+impl<'a> History<'a> {
+    pub const fn new(
+        entries_prefix: &'a str,
+        kind_index_prefix: &'a str,
+        count_prefix: &'a str,
+    ) -> Self {
+        Self {
+            entries: IndexedMap::new(
+                entries_prefix,
+                HistoryIndexes {
+                    kind: MultiIndex::new(
+                        kind_index,
+                        entries_prefix,
+                        kind_index_prefix,
+                    ),
+                },
+            ),
+            count: Item::new(count_prefix),
+        }
+    }
+
+    /// Appends an entry to the ledger and returns it.
+    pub fn record(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        kind: HistoryKind,
+        to: impl Into<String>,
+        amount: Uint128,
+    ) -> StdResult<HistoryEntry> {
+        let seq = self.count.may_load(storage)?.unwrap_or_default() + 1;
+        let entry = HistoryEntry {
+            seq,
+            kind,
+            amount,
+            to: to.into(),
+            height: block.height,
+            time: block.time,
+        };
+        self.count.save(storage, &seq)?;
+        self.entries.save(storage, seq, &entry)?;
+        Ok(entry)
+    }
+
+    /// Records the token transfers in MSGS. Transfers to the
+    /// Community Pool are recorded as clawbacks, all others as KIND.
+    pub fn record_msgs(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        kind: HistoryKind,
+        msgs: &[CosmosMsg],
+    ) -> StdResult<Vec<HistoryEntry>> {
+        let mut entries = vec![];
+        for msg in msgs {
+            let (kind, to, amount) = match msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => (
+                    kind.clone(),
+                    to_address.clone(),
+                    amount.iter().map(|c| c.amount).sum(),
+                ),
+                CosmosMsg::Ibc(IbcMsg::Transfer {
+                    to_address, amount, ..
+                }) => (kind.clone(), to_address.clone(), amount.amount),
+                CosmosMsg::Distribution(DistributionMsg::FundCommunityPool { amount }) => (
+                    HistoryKind::Clawback,
+                    COMMUNITY_POOL.to_string(),
+                    amount.iter().map(|c| c.amount).sum(),
+                ),
+                _ => continue,
+            };
+            entries.push(self.record(storage, block, kind, to, amount)?);
+        }
+        Ok(entries)
+    }
+
+    /// Lists ledger entries in ascending order, optionally only those
+    /// of KIND.
+    pub fn list(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        kind: Option<HistoryKind>,
+    ) -> StdResult<Vec<HistoryEntry>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.map(Bound::exclusive);
+        let entries = match kind {
+            Some(kind) => self
+                .entries
+                .idx
+                .kind
+                .prefix(kind.as_str().to_string())
+                .range(storage, min, None, Order::Ascending),
+            None => self.entries.range(storage, min, None, Order::Ascending),
+        };
+        entries
+            .take(limit)
+            .map(|e| e.map(|(_, entry)| entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coins,
+        testing::{mock_dependencies, mock_env},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_list() {
+        let storage = &mut mock_dependencies().storage;
+        let block = mock_env().block;
+        let history = History::new("history", "history__kind", "history_count");
+
+        history
+            .record(storage, &block, HistoryKind::Fund, "contract", Uint128::new(100))
+            .unwrap();
+        history
+            .record_msgs(
+                storage,
+                &block,
+                HistoryKind::CancelPayout,
+                &[
+                    BankMsg::Send {
+                        to_address: "recv".to_string(),
+                        amount: coins(40, "uluna"),
+                    }
+                    .into(),
+                    DistributionMsg::FundCommunityPool {
+                        amount: coins(60, "uluna"),
+                    }
+                    .into(),
+                ],
+            )
+            .unwrap();
+
+        let all = history.list(storage, None, None, None).unwrap();
+        assert_eq!(
+            all.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(all[1].kind, HistoryKind::CancelPayout);
+        assert_eq!(all[1].to, "recv");
+        assert_eq!(all[1].height, block.height);
+
+        let clawbacks = history
+            .list(storage, None, None, Some(HistoryKind::Clawback))
+            .unwrap();
+        assert_eq!(clawbacks.len(), 1);
+        assert_eq!(clawbacks[0].to, COMMUNITY_POOL);
+        assert_eq!(clawbacks[0].amount, Uint128::new(60));
+
+        let page = history.list(storage, Some(1), Some(1), None).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].seq, 2);
+    }
+}
@@ -1,7 +1,15 @@
 pub mod denom;
 pub mod payment;
 pub mod error;
+pub mod events;
+pub mod history;
 pub mod state;
 pub mod contract;
 pub mod msg;
-pub mod payment_test;
\ No newline at end of file
+pub mod payment_test;
+#[cfg(test)]
+mod contract_test;
+#[cfg(test)]
+mod payment_proptest;
+#[cfg(test)]
+mod suite_tests;
@@ -1,9 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Timestamp, Uint128};
+use cosmwasm_std::{Decimal, Timestamp, Uint128};
 use crate::denom::UncheckedDenom;
+use crate::history::HistoryKind;
 use cw_ownable::cw_ownable_execute;
 
-use crate::payment::Schedule;
+use crate::payment::{IbcPayout, KeeperConfig, Schedule, Status, UncheckedClaimPolicy};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -41,6 +42,29 @@ pub struct InstantiateMsg {
     /// See `suite_tests/tests.rs`
     /// `test_almost_instavest_in_the_future` for an example of this.
     pub vesting_duration_seconds: u64,
+    /// An optional tip paid to third parties that call `Distribute`
+    /// on behalf of the recipient, so that vested funds are streamed
+    /// regularly even if the recipient forgets to claim.
+    pub keeper: Option<KeeperConfig>,
+    /// The notice period in seconds between the owner cancelling the
+    /// payment and the cancellation taking effect. Vesting continues
+    /// during the notice period. `None` cancels instantly.
+    pub cancel_notice_seconds: Option<u64>,
+    /// An optional guardian, e.g. a council multisig, that can
+    /// freeze distributions in an emergency without waiting for a
+    /// governance proposal.
+    pub guardian: Option<String>,
+    /// Whether the owner may transfer ownership of the contract, e.g.
+    /// to a successor governance or DAO contract. Transfers are two
+    /// step (the new owner has to accept) and may expire. Defaults to
+    /// `false`, in which case the ownership can never change.
+    pub allow_ownership_transfer: Option<bool>,
+    /// The number of seconds after the end of the vesting schedule,
+    /// or its cancellation, after which tokens the recipient has not
+    /// claimed may be returned to the Community Pool, e.g. if the
+    /// recipient lost access to their wallet. `None` keeps them
+    /// claimable forever.
+    pub claim_deadline_seconds: Option<u64>,
 }
 
 #[cw_ownable_execute]
@@ -48,10 +72,14 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     /// After the contract has received the exact amount of tokens
     /// to be vested, anyone can call this method to mark the contract
-    /// as funded so that the vesting schedule can become active.
+    /// as funded so that the vesting schedule can become active. The
+    /// same applies to the additional tokens of an extension.
     Fund {},
-    /// Distribute vested tokens to the vest receiver. Anyone may call
-    /// this method.
+    /// Distribute vested tokens to the vest receiver. Who may call
+    /// this method depends on the claim policy of the vest. If a
+    /// keeper incentive is configured, callers other than the
+    /// receiver are paid a tip out of the distributed amount and are
+    /// rate limited.
     Distribute {
         /// The amount of tokens to distribute. If none are specified
         /// all claimable tokens will be distributed.
@@ -63,7 +91,83 @@ pub enum ExecuteMsg {
     ///
     /// The amounts that the vestee and the Community Pool are entitled
     /// to are calculated and transferred to the respective parties.
+    ///
+    /// If the payment has a cancellation notice period, the
+    /// cancellation is only scheduled and takes effect at the end of
+    /// the notice period, see `FinalizeCancel`.
     Cancel {},
+    /// Settles a scheduled cancellation once its notice period has
+    /// passed. Anyone may call this method.
+    FinalizeCancel {},
+    /// Revokes a scheduled cancellation. Only the owner may call this
+    /// method.
+    RevokeCancel {},
+    /// Reduces the total amount to vest without cancelling the
+    /// payment. The remaining part of the schedule is scaled down
+    /// proportionally and the freed tokens are sent to the Community
+    /// Pool. Tokens that already vested are not affected. Rejected
+    /// while a cancellation is pending. Only the owner may call this
+    /// method.
+    Reduce { new_total: Uint128 },
+    /// Extends the payment by appending another vesting schedule
+    /// after the current end of the schedule, e.g. to renew a team
+    /// for another term. Tokens claimed so far are not affected.
+    ///
+    /// The additional tokens need to be sent to the contract before
+    /// `Fund` can be called again to activate the extension. Until
+    /// then the previously funded tokens keep vesting. Only the owner
+    /// may call this method.
+    Extend {
+        /// The number of tokens added to the total amount to vest.
+        additional_total: Uint128,
+        /// The length of the appended schedule in seconds.
+        additional_duration_seconds: u64,
+        /// The appended schedule. `seconds` of `PiecewiseLinear`
+        /// points are relative to the end of the current schedule,
+        /// or to the block time if the current schedule has already
+        /// ended.
+        schedule: Schedule,
+    },
+    /// Freezes distributions for `duration_seconds`, e.g. if the
+    /// recipient's key is compromised. The guardian may freeze for
+    /// at most two weeks, can not extend an active freeze and can not
+    /// freeze again until 30 days after its last freeze ended, unless
+    /// the owner reappoints it. The owner may freeze for any duration.
+    Freeze { duration_seconds: u64 },
+    /// Lifts an active freeze. Only the owner may call this method.
+    Unfreeze {},
+    /// Replaces the guardian, or removes it if `None` is given. This
+    /// also lets the guardian freeze again right away. Only the owner
+    /// may call this method.
+    UpdateGuardian { guardian: Option<String> },
+    /// Sets the remote chain destination for distributions, or
+    /// clears it if `None` is given. Failed transfers are refunded to
+    /// the contract, see `IbcPayout`. Only the vest receiver may call
+    /// this method.
+    SetIbcPayout { payout: Option<IbcPayout> },
+    /// Pays tokens of failed IBC distributions that were refunded to
+    /// the contract to the vest receiver's local address. Any balance
+    /// beyond what is held for the vestee counts as refunded, up to
+    /// the amount distributed over IBC. Only the vest receiver may
+    /// call this method.
+    ClaimIbcRefund {},
+    /// Restricts who may call `Distribute`, e.g. to control the
+    /// timing of payouts. Only the vest receiver may call this
+    /// method.
+    SetClaimPolicy { policy: UncheckedClaimPolicy },
+    /// Closes the contract once every token has been claimed or the
+    /// payment has been cancelled. Any remaining balance, e.g. dust or
+    /// accidental transfers, is sent to the Community Pool. All
+    /// further executions fail. Not possible until a week after the
+    /// timeout of the last IBC distribution, so that refunds can be
+    /// claimed. Anyone may call this method.
+    Close {},
+    /// Once the claim deadline has passed, returns the remaining
+    /// balance, including vested but unclaimed tokens, to the
+    /// Community Pool and closes the contract. Like `Close`, not
+    /// possible while IBC distributions may be refunded. Anyone may
+    /// call this method.
+    ReclaimUnclaimed {},
 }
 
 #[cw_serde]
@@ -100,6 +204,104 @@ pub enum QueryMsg {
     /// completing. Returns `None` if the vest has been cancelled.
     #[returns(Option<::cosmwasm_std::Uint64>)]
     VestDuration {},
+    /// Gets the operators that may distribute on behalf of the vest
+    /// receiver.
+    #[returns(Vec<::cosmwasm_std::Addr>)]
+    Operators {},
+    /// Lists the payment history, i.e. every funding, distribution,
+    /// cancellation payout, clawback and refund, in the order they
+    /// happened.
+    #[returns(Vec<crate::history::HistoryEntry>)]
+    History {
+        /// The sequence number to start after.
+        start_after: Option<u64>,
+        /// The maximum number of entries to return, at most 30.
+        limit: Option<u32>,
+        /// Only return entries of this kind.
+        kind: Option<HistoryKind>,
+    },
+    /// Samples `vested(t)` at `points` evenly spaced times between
+    /// `from` and `to` (both inclusive), e.g. to draw a vesting
+    /// chart. At most 200 points may be requested.
+    #[returns(Vec<crate::payment::VestingPoint>)]
+    Timeline {
+        from: Timestamp,
+        to: Timestamp,
+        points: u32,
+    },
+    /// Gets the vertices of the vesting curve as absolute timestamps.
+    /// Between two vertices, the vested amount is linearly
+    /// interpolated. Pending cancellations and unfunded extensions
+    /// are taken into account.
+    #[returns(Vec<crate::payment::VestingPoint>)]
+    Breakpoints {},
+    /// Summarises the state of the payment at time `t`, or the current
+    /// time if `t` is `None`.
+    #[returns(SummaryResponse)]
+    Summary { t: Option<Timestamp> },
+    /// Gets the next time after `after` (or the current time) at
+    /// which a vertex of the vesting curve releases tokens, together
+    /// with the amount vesting until then. For step schedules this is
+    /// the next jump. Returns `None` if nothing vests anymore, e.g.
+    /// after cancellation.
+    #[returns(Option<crate::payment::Release>)]
+    NextRelease { after: Option<Timestamp> },
+    /// Lists the vertices of the vesting curve that release tokens,
+    /// with the amount released since the previous vertex.
+    #[returns(Vec<crate::payment::Release>)]
+    ReleaseCalendar {
+        /// The release time to start after.
+        start_after: Option<Timestamp>,
+        /// The maximum number of releases to return, at most 30.
+        limit: Option<u32>,
+    },
+    /// Checks the accounting invariants of the payment at the current
+    /// time, for monitoring. See `InvariantsResponse`.
+    #[returns(InvariantsResponse)]
+    Invariants {},
+    /// Gets the time after which unclaimed tokens may be returned to
+    /// the Community Pool, or `None` if there is no claim deadline.
+    #[returns(Option<::cosmwasm_std::Timestamp>)]
+    ClaimDeadline {},
+}
+
+#[cw_serde]
+pub struct SummaryResponse {
+    pub status: Status,
+    /// The total amount that will ever vest.
+    pub total: Uint128,
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    pub distributable: Uint128,
+    /// The contract's balance of the vesting denom.
+    pub balance: Uint128,
+    /// The balance in excess of what is owed to the vestee, i.e. the
+    /// funded part of the total that has not been claimed or, after
+    /// cancellation, the `debt`.
+    pub surplus: Uint128,
+    /// The amount the balance is short of what is owed to the vestee.
+    pub shortfall: Uint128,
+    pub start_time: Timestamp,
+    /// The time of the last vertex of the vesting curve or, once
+    /// cancelled, the time of the cancellation.
+    pub end_time: Timestamp,
+    /// The share of the total that has vested, in [0, 1].
+    pub percent_complete: Decimal,
+    /// The time of the next release, if any.
+    pub next_release: Option<Timestamp>,
+    /// The time after which unclaimed tokens may be returned to the
+    /// Community Pool, if any.
+    pub claim_deadline: Option<Timestamp>,
+}
+
+/// The result of checking `claimed <= vested(now) <= total` and, while
+/// funded, `balance >= total - claimed`.
+#[cw_serde]
+pub struct InvariantsResponse {
+    /// Whether all invariants hold.
+    pub ok: bool,
+    /// A description of each violated invariant.
+    pub violations: Vec<String>,
 }
 
 #[cw_serde]
@@ -112,4 +314,4 @@ pub struct MigrateWithdrawBalance {
 #[cw_serde]
 pub struct MigrateMsg {
     pub withdraw: Option<MigrateWithdrawBalance>,
-}
\ No newline at end of file
+}
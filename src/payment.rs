@@ -1,7 +1,10 @@
-use std::cmp::min;
+use std::cmp::{max, min, Ordering};
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, CosmosMsg, StdResult, Storage, Timestamp, Uint128, Uint64};
+use cosmwasm_std::{
+    Addr, Api, CosmosMsg, Decimal, IbcTimeout, StdError, StdResult, Storage, Timestamp, Uint128,
+    Uint64,
+};
 use crate::denom::CheckedDenom;
 use cw_storage_plus::Item;
 use wynd_utils::{Curve, PiecewiseLinear, SaturatingLinear};
@@ -24,16 +27,162 @@ pub struct Vest {
 
     /// The number of tokens that have been claimed by the vest receiver.
     pub claimed: Uint128,
+    /// Vested tokens that could not be paid out on cancellation
+    /// because the contract's balance fell short, e.g. after a
+    /// slash. They may be distributed once the contract holds them.
+    pub debt: Uint128,
 
     pub title: String,
     pub description: Option<String>,
+
+    /// Optional remote destination for distributions, configured by
+    /// the vest receiver.
+    pub ibc_payout: Option<IbcPayout>,
+    /// Tokens distributed over IBC that may be refunded to the
+    /// contract if their transfer fails.
+    pub ibc_in_flight: Uint128,
+    /// The contract may not be closed before this time, so that
+    /// refunds of IBC distributions can still be claimed.
+    pub ibc_refund_until: Option<Timestamp>,
+
+    /// Optional incentive for third parties distributing on behalf
+    /// of the vest receiver.
+    pub keeper: Option<KeeperConfig>,
+    /// The time of the last distribution triggered by a keeper.
+    pub last_keeper_distribution: Option<Timestamp>,
+
+    /// Who may call `Distribute`, set by the vest receiver.
+    pub claim_policy: ClaimPolicy,
+
+    /// The notice period between the owner cancelling the vest and
+    /// the cancellation taking effect.
+    pub cancel_notice_seconds: u64,
+    /// The time at which a scheduled cancellation takes effect.
+    /// Nothing vests after this time.
+    pub pending_cancel: Option<Timestamp>,
+    /// The time the cancellation took effect.
+    pub canceled_at: Option<Timestamp>,
+
+    /// The part of the total that has been added by an extension but
+    /// has not been funded yet.
+    pub top_up: Uint128,
+
+    /// An optional address, e.g. a council multisig, that may freeze
+    /// distributions in an emergency.
+    pub guardian: Option<Addr>,
+    /// Distributions are frozen until this time.
+    pub frozen_until: Option<Timestamp>,
+    /// The guardian may not freeze distributions again before this
+    /// time, unless governance reappoints it.
+    pub guardian_cooldown_until: Option<Timestamp>,
+
+    /// The number of seconds after the end of the vest after which
+    /// unclaimed tokens may be returned to the Community Pool.
+    pub claim_deadline_seconds: Option<u64>,
+}
+
+/// The longest freeze the guardian may impose. Long enough for a
+/// governance proposal to pass, after which governance decides.
+pub const MAX_GUARDIAN_FREEZE_SECONDS: u64 = 14 * 24 * 60 * 60;
+
+/// The time after a guardian freeze ends before the guardian may
+/// freeze again, so that it can not keep distributions frozen without
+/// governance.
+pub const GUARDIAN_FREEZE_COOLDOWN_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// The time after the timeout of an IBC distribution during which the
+/// contract expects a refund and can not be closed. Relayers deliver
+/// timeouts well within this window.
+pub const IBC_REFUND_GRACE_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// The maximum number of samples returned by a timeline query.
+pub const MAX_TIMELINE_POINTS: u32 = 200;
+
+/// The amount vested at a point in time.
+#[cw_serde]
+pub struct VestingPoint {
+    pub time: Timestamp,
+    pub vested: Uint128,
+}
+
+const DEFAULT_CALENDAR_LIMIT: u32 = 10;
+const MAX_CALENDAR_LIMIT: u32 = 30;
+
+/// An amount becoming vested at a point in time.
+#[cw_serde]
+pub struct Release {
+    pub time: Timestamp,
+    pub amount: Uint128,
+}
+
+/// Incentive for keepers, i.e. anyone other than the vest receiver
+/// calling `Distribute`. The tip is deducted from the distributed
+/// amount and sent to the keeper.
+#[cw_serde]
+pub struct KeeperConfig {
+    /// The share of each keeper-triggered distribution paid to the
+    /// keeper. Must be at most 10%.
+    pub tip_rate: Decimal,
+    /// The maximum tip paid for a single distribution.
+    pub max_tip: Uint128,
+    /// The minimum number of seconds between two keeper-triggered
+    /// distributions.
+    pub min_interval_seconds: u64,
+}
+
+/// Describes where vested tokens are sent when the vest receiver
+/// wants to be paid on another chain. Distributions are sent as
+/// ICS20 transfers. The transfer module owns the channel, so the
+/// contract is never told whether a transfer arrived. If it fails or
+/// times out, the tokens are refunded to the contract as plain
+/// balance. The vest receiver may claim them back with
+/// `ClaimIbcRefund`, up to the amount sent over IBC.
+///
+/// Payouts on cancellation are always made to the local `recipient`.
+#[cw_serde]
+pub struct IbcPayout {
+    /// The channel on this chain that the transfer is sent over.
+    pub channel_id: String,
+    /// The receiving address on the remote chain.
+    pub remote_address: String,
+    /// The number of seconds after which an unrelayed transfer
+    /// times out.
+    pub timeout_seconds: u64,
+}
+
+/// Restricts who may trigger distributions to the vest receiver. The
+/// receiver itself may always distribute.
+#[cw_serde]
+pub enum ClaimPolicy {
+    /// Anyone may distribute.
+    Anyone,
+    /// Only the vest receiver may distribute.
+    RecipientOnly,
+    /// The vest receiver and the listed operators may distribute.
+    Operators(Vec<Addr>),
+}
+
+/// A claim policy whose operator addresses have not been validated.
+#[cw_serde]
+pub enum UncheckedClaimPolicy {
+    Anyone,
+    RecipientOnly,
+    Operators(Vec<String>),
 }
 
 #[cw_serde]
 pub enum Status {
     Unfunded,
     Funded,
+    /// The vest has been extended and the additional tokens have not
+    /// arrived yet. The previously funded part keeps vesting.
+    AwaitingTopUp,
     Canceled,
+    /// Every token has been claimed by the vest receiver.
+    Completed,
+    /// The remaining balance has been swept to the Community Pool.
+    /// The contract accepts no further executions.
+    Closed,
 }
 
 #[cw_serde]
@@ -64,6 +213,10 @@ pub struct VestInit {
     pub recipient: Addr,
     pub title: String,
     pub description: Option<String>,
+    pub keeper: Option<KeeperConfig>,
+    pub cancel_notice_seconds: u64,
+    pub guardian: Option<Addr>,
+    pub claim_deadline_seconds: Option<u64>,
 }
 
 impl<'a> Payment<'a> {
@@ -96,7 +249,9 @@ impl<'a> Payment<'a> {
         match vesting.status {
             Status::Unfunded => Uint128::zero(),
             Status::Funded => vesting.total() - vesting.claimed,
-            Status::Canceled => Uint128::zero(),
+            Status::AwaitingTopUp => vesting.total() - vesting.top_up - vesting.claimed,
+            Status::Canceled => vesting.debt,
+            Status::Completed | Status::Closed => Uint128::zero(),
         }
     }
 
@@ -123,30 +278,146 @@ impl<'a> Payment<'a> {
         t: Timestamp,
         request: Option<Uint128>,
     ) -> Result<CosmosMsg, ContractError> {
+        let (mut vesting, request) = self.claim(storage, t, request)?;
+        let msg = vesting.payout_message(request, t)?;
+        self.vesting.save(storage, &vesting)?;
+        Ok(msg)
+    }
+
+    /// Distributes vested tokens on behalf of KEEPER. If the vest has
+    /// a keeper incentive configured, the minimum interval between
+    /// keeper distributions is enforced and the keeper's tip is
+    /// deducted from the distributed amount. Otherwise this behaves
+    /// like `distribute`.
+    pub fn keeper_distribute(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        request: Option<Uint128>,
+        keeper: &Addr,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
         let vesting = self.vesting.load(storage)?;
+        let config = match vesting.keeper {
+            Some(config) => config,
+            None => return Ok(vec![self.distribute(storage, t, request)?]),
+        };
+        if let Some(last) = vesting.last_keeper_distribution {
+            let next = last.plus_seconds(config.min_interval_seconds);
+            if t < next {
+                return Err(ContractError::KeeperTooEarly { next });
+            }
+        }
 
-        let distributable = self.distributable(storage, &vesting, t)?;
-        let request = request.unwrap_or(distributable);
+        let (mut vesting, request) = self.claim(storage, t, request)?;
+        vesting.last_keeper_distribution = Some(t);
 
-        let mut vesting = vesting;
-        vesting.claimed += request;
+        let tip = min(request * config.tip_rate, config.max_tip);
+        let mut msgs = vec![];
+        if request > tip {
+            msgs.push(vesting.payout_message(request - tip, t)?);
+        }
+        if !tip.is_zero() {
+            msgs.push(vesting.denom.get_transfer_to_message(keeper, tip)?);
+        }
         self.vesting.save(storage, &vesting)?;
+        Ok(msgs)
+    }
+
+    /// Marks REQUEST tokens (or all distributable tokens if `None`) as
+    /// claimed and returns the updated vest together with the amount.
+    /// The request is validated before anything is written, so a
+    /// failed claim leaves the storage untouched.
+    fn claim(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        request: Option<Uint128>,
+    ) -> Result<(Vest, Uint128), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        if let Some(until) = vesting.frozen_until {
+            if t < until {
+                return Err(ContractError::Frozen { until });
+            }
+        }
 
+        let distributable = self.distributable(storage, &vesting, t)?;
+        let request = request.unwrap_or(distributable);
         if request > distributable || request.is_zero() {
-            Err(ContractError::InvalidWithdrawal {
+            return Err(ContractError::InvalidWithdrawal {
                 request,
                 claimable: distributable,
-            })
-        } else {
-            Ok(vesting
-                .denom
-                .get_transfer_to_message(&vesting.recipient, request)?)
+            });
+        }
+
+        vesting.claimed += request;
+        if vesting.status == Status::Funded && vesting.claimed == vesting.total() {
+            vesting.status = Status::Completed;
+        }
+        if vesting.status == Status::Canceled {
+            vesting.debt -= request;
         }
+        self.vesting.save(storage, &vesting)?;
+        Ok((vesting, request))
+    }
+
+    /// Pays refunded IBC distributions to the vest receiver's local
+    /// address. Refunds arrive as plain balance, so the part of
+    /// TOTAL_BALANCE that is not held for the vestee counts as
+    /// refunded, up to the tokens still in flight.
+    pub fn claim_ibc_refund(
+        &self,
+        storage: &mut dyn Storage,
+        total_balance: Uint128,
+    ) -> Result<CosmosMsg, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        // a pending top-up is held for the vestee as well
+        let held = match vesting.status {
+            Status::Funded | Status::AwaitingTopUp => vesting.total() - vesting.claimed,
+            Status::Canceled => vesting.debt,
+            Status::Unfunded | Status::Completed => Uint128::zero(),
+            Status::Closed => return Err(ContractError::Closed),
+        };
+        let refund = min(vesting.ibc_in_flight, total_balance.saturating_sub(held));
+        if refund.is_zero() {
+            return Err(ContractError::NoIbcRefund);
+        }
+        vesting.ibc_in_flight -= refund;
+        self.vesting.save(storage, &vesting)?;
+        Ok(vesting.denom.get_transfer_to_message(&vesting.recipient, refund)?)
+    }
+
+    /// Sets or clears the remote destination for distributions.
+    pub fn set_ibc_payout(
+        &self,
+        storage: &mut dyn Storage,
+        payout: Option<IbcPayout>,
+    ) -> Result<(), ContractError> {
+        if let Some(ref p) = payout {
+            p.validate()?;
+        }
+        let mut vesting = self.vesting.load(storage)?;
+        vesting.ibc_payout = payout;
+        self.vesting.save(storage, &vesting)?;
+        Ok(())
+    }
+
+    /// Replaces the claim policy of the vest.
+    pub fn set_claim_policy(
+        &self,
+        storage: &mut dyn Storage,
+        policy: ClaimPolicy,
+    ) -> Result<(), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        vesting.claim_policy = policy;
+        self.vesting.save(storage, &vesting)?;
+        Ok(())
     }
 
     /// Cancels the vesting payment. The current amount vested becomes
     /// the total amount that will ever vest. note that canceling does
-    /// not impact already vested tokens.
+    /// not impact already vested tokens. If TOTAL_BALANCE does not
+    /// cover them, the vestee is paid the balance and the shortfall
+    /// is recorded as `debt`.
     pub fn cancel(
         &self,
         storage: &mut dyn Storage,
@@ -160,8 +431,10 @@ impl<'a> Payment<'a> {
 
             let mut msgs = vec![];
 
-            // the outstanding amount that the vestee is entitled to
-            let to_vestee = vesting.vested(t) - vesting.claimed;
+            // the outstanding amount that the vestee is entitled to,
+            // as far as the balance covers it
+            let entitled = vesting.vested(t) - vesting.claimed;
+            let to_vestee = min(entitled, total_balance);
             if to_vestee > Uint128::zero() {
                 msgs.push(
                     vesting
@@ -177,16 +450,257 @@ impl<'a> Payment<'a> {
             }
 
             vesting.cancel(t);
+            vesting.debt = entitled - to_vestee;
             self.vesting.save(storage, &vesting)?;
 
             Ok(msgs)
         }
     }
 
+    /// Schedules the cancellation of the vesting payment at the end
+    /// of the notice period starting at `t`. Vesting continues until
+    /// then. Returns the time the cancellation takes effect.
+    pub fn schedule_cancel(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+    ) -> Result<Timestamp, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        if matches!(vesting.status, Status::Canceled) {
+            return Err(ContractError::Cancelled {});
+        }
+        if let Some(effective) = vesting.pending_cancel {
+            return Err(ContractError::CancelPending { effective });
+        }
+        let effective = t.plus_seconds(vesting.cancel_notice_seconds);
+        vesting.pending_cancel = Some(effective);
+        self.vesting.save(storage, &vesting)?;
+        Ok(effective)
+    }
+
+    /// Settles a scheduled cancellation once its notice period has
+    /// passed. See `cancel`.
+    pub fn finalize_cancel(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        total_balance: Uint128,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        let vesting = self.vesting.load(storage)?;
+        match vesting.pending_cancel {
+            None => Err(ContractError::NoPendingCancel),
+            Some(effective) if t < effective => {
+                Err(ContractError::CancelNoticeActive { effective })
+            }
+            Some(effective) => self.cancel(storage, effective, total_balance),
+        }
+    }
+
+    /// Revokes a scheduled cancellation. The vest continues as if it
+    /// had never been cancelled.
+    pub fn revoke_cancel(&self, storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        if vesting.pending_cancel.is_none() {
+            return Err(ContractError::NoPendingCancel);
+        }
+        vesting.pending_cancel = None;
+        self.vesting.save(storage, &vesting)?;
+        Ok(())
+    }
+
+    /// Lowers the total amount to vest to NEW_TOTAL by scaling down
+    /// the part of the curve after `t`. Tokens vested at `t` are not
+    /// affected. Returns the message sending the freed tokens to the
+    /// Community Pool.
+    pub fn reduce(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        new_total: Uint128,
+    ) -> Result<CosmosMsg, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        match vesting.status {
+            Status::Unfunded => return Err(ContractError::NotFunded),
+            Status::Funded => (),
+            Status::AwaitingTopUp => return Err(ContractError::AwaitingTopUp),
+            Status::Canceled => return Err(ContractError::Cancelled),
+            Status::Completed | Status::Closed => return Err(ContractError::Completed),
+        }
+        if let Some(effective) = vesting.pending_cancel {
+            return Err(ContractError::CancelPending { effective });
+        }
+        let freed = vesting.reduce(t, new_total)?;
+        // nothing is left to claim if the total drops to what has
+        // been claimed already
+        if vesting.claimed == vesting.total() {
+            vesting.status = Status::Completed;
+        }
+        self.vesting.save(storage, &vesting)?;
+        Ok(vesting.denom.get_fund_cp_message(freed)?)
+    }
+
+    /// Extends the vest by appending SCHEDULE, vesting ADDITIONAL_TOTAL
+    /// tokens over DURATION_SECONDS, after the current end of the
+    /// curve (or after `t` if the curve has already ended). The vest
+    /// awaits funding of the additional tokens afterwards.
+    pub fn extend(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        additional_total: Uint128,
+        duration_seconds: u64,
+        schedule: Schedule,
+    ) -> Result<Vest, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        match vesting.status {
+            Status::Unfunded => return Err(ContractError::NotFunded),
+            Status::Funded | Status::Completed => (),
+            Status::AwaitingTopUp => return Err(ContractError::AwaitingTopUp),
+            Status::Canceled => return Err(ContractError::Cancelled),
+            Status::Closed => return Err(ContractError::Closed),
+        }
+        if let Some(effective) = vesting.pending_cancel {
+            return Err(ContractError::CancelPending { effective });
+        }
+        vesting.extend(t, additional_total, duration_seconds, schedule)?;
+        self.vesting.save(storage, &vesting)?;
+        Ok(vesting)
+    }
+
+    /// Freezes distributions for DURATION_SECONDS starting at `t`.
+    /// Replaces any active freeze. Returns the time the freeze ends.
+    pub fn freeze(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        duration_seconds: u64,
+    ) -> Result<Timestamp, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        let until = t.plus_seconds(duration_seconds);
+        vesting.frozen_until = Some(until);
+        self.vesting.save(storage, &vesting)?;
+        Ok(until)
+    }
+
+    /// Freezes distributions on behalf of the guardian, which may
+    /// freeze for at most `MAX_GUARDIAN_FREEZE_SECONDS`, not extend an
+    /// active freeze and not freeze again during the cooldown after
+    /// its last freeze. Returns the time the freeze ends.
+    pub fn guardian_freeze(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        duration_seconds: u64,
+    ) -> Result<Timestamp, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        if duration_seconds > MAX_GUARDIAN_FREEZE_SECONDS || vesting.is_frozen(t) {
+            return Err(ContractError::GuardianFreezeLimit {
+                max: MAX_GUARDIAN_FREEZE_SECONDS,
+            });
+        }
+        if let Some(until) = vesting.guardian_cooldown_until.filter(|until| t < *until) {
+            return Err(ContractError::GuardianCooldown { until });
+        }
+        let until = t.plus_seconds(duration_seconds);
+        vesting.frozen_until = Some(until);
+        vesting.guardian_cooldown_until = Some(until.plus_seconds(GUARDIAN_FREEZE_COOLDOWN_SECONDS));
+        self.vesting.save(storage, &vesting)?;
+        Ok(until)
+    }
+
+    /// Lifts an active freeze.
+    pub fn unfreeze(&self, storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        vesting.frozen_until = None;
+        self.vesting.save(storage, &vesting)?;
+        Ok(())
+    }
+
+    /// Replaces or removes the guardian. Resets the cooldown of the
+    /// guardian's freezes.
+    pub fn set_guardian(
+        &self,
+        storage: &mut dyn Storage,
+        guardian: Option<Addr>,
+    ) -> Result<(), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        vesting.guardian = guardian;
+        vesting.guardian_cooldown_until = None;
+        self.vesting.save(storage, &vesting)?;
+        Ok(())
+    }
+
+    /// Closes a completed or cancelled vest. Returns the message
+    /// sweeping TOTAL_BALANCE, i.e. dust and accidental transfers, to
+    /// the Community Pool. A cancelled vest may not be closed while
+    /// the vestee is owed tokens, and no vest may be closed at `t`
+    /// while IBC distributions may still be refunded.
+    pub fn close(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        total_balance: Uint128,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        match vesting.status {
+            Status::Completed | Status::Canceled => (),
+            Status::Closed => return Err(ContractError::Closed),
+            Status::Unfunded | Status::Funded | Status::AwaitingTopUp => {
+                return Err(ContractError::NotCompleted)
+            }
+        }
+        if !vesting.debt.is_zero() {
+            return Err(ContractError::Debt { debt: vesting.debt });
+        }
+        vesting.assert_no_ibc_refund(t)?;
+
+        let mut msgs = vec![];
+        if !total_balance.is_zero() {
+            msgs.push(vesting.denom.get_fund_cp_message(total_balance)?);
+        }
+        vesting.status = Status::Closed;
+        self.vesting.save(storage, &vesting)?;
+        Ok(msgs)
+    }
+
+    /// Returns TOTAL_BALANCE to the Community Pool once the claim
+    /// deadline has passed at `t` and closes the vest. Like `close`,
+    /// this waits for refunds of IBC distributions.
+    pub fn reclaim_unclaimed(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        total_balance: Uint128,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        match vesting.status {
+            Status::Unfunded => return Err(ContractError::NotFunded),
+            Status::Closed => return Err(ContractError::Closed),
+            Status::Funded | Status::AwaitingTopUp | Status::Canceled | Status::Completed => (),
+        }
+        match vesting.claim_deadline() {
+            None => return Err(ContractError::NoClaimDeadline),
+            Some(deadline) if t < deadline => {
+                return Err(ContractError::ClaimDeadlineNotReached { deadline })
+            }
+            Some(_) => (),
+        }
+        vesting.assert_no_ibc_refund(t)?;
+
+        let mut msgs = vec![];
+        if !total_balance.is_zero() {
+            msgs.push(vesting.denom.get_fund_cp_message(total_balance)?);
+        }
+        vesting.status = Status::Closed;
+        self.vesting.save(storage, &vesting)?;
+        Ok(msgs)
+    }
+
     pub fn set_funded(&self, storage: &mut dyn Storage) -> Result<(), ContractError> {
         let mut v = self.vesting.load(storage)?;
-        debug_assert!(v.status == Status::Unfunded);
+        debug_assert!(matches!(v.status, Status::Unfunded | Status::AwaitingTopUp));
         v.status = Status::Funded;
+        v.top_up = Uint128::zero();
         self.vesting.save(storage, &v)?;
         Ok(())
     }
@@ -204,9 +718,16 @@ impl Vest {
             Err(ContractError::ZeroVest)
         } else if init.duration_seconds == 0 {
             Err(ContractError::Instavest)
+        } else if init
+            .keeper
+            .as_ref()
+            .is_some_and(|k| k.tip_rate > Decimal::percent(10))
+        {
+            Err(ContractError::KeeperTipTooHigh)
         } else {
             Ok(Self {
                 claimed: Uint128::zero(),
+                debt: Uint128::zero(),
                 vested: init
                     .schedule
                     .into_curve(init.total, init.duration_seconds)?,
@@ -216,6 +737,20 @@ impl Vest {
                 status: Status::Unfunded,
                 title: init.title,
                 description: init.description,
+                ibc_payout: None,
+                ibc_in_flight: Uint128::zero(),
+                ibc_refund_until: None,
+                keeper: init.keeper,
+                last_keeper_distribution: None,
+                claim_policy: ClaimPolicy::Anyone,
+                cancel_notice_seconds: init.cancel_notice_seconds,
+                pending_cancel: None,
+                canceled_at: None,
+                top_up: Uint128::zero(),
+                guardian: init.guardian,
+                frozen_until: None,
+                guardian_cooldown_until: None,
+                claim_deadline_seconds: init.claim_deadline_seconds,
             })
         }
     }
@@ -226,10 +761,319 @@ impl Vest {
         Uint128::new(self.vested.range().1)
     }
 
-    /// Gets the number of tokens that have vested at `time`.
+    /// Gets the time vesting starts.
+    pub fn start_time(&self) -> Timestamp {
+        self.start_time
+    }
+
+    /// Gets the number of tokens that have vested at `time`. If a
+    /// cancellation is pending, nothing vests after it takes effect.
+    /// Tokens of an extension that has not been funded yet do not
+    /// vest.
     pub fn vested(&self, t: Timestamp) -> Uint128 {
+        let t = match self.pending_cancel {
+            Some(effective) => min(t, effective),
+            None => t,
+        };
+        let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
+        min(self.vested.value(elapsed), self.total() - self.top_up)
+    }
+
+    /// Returns whether distributions are frozen at `t`.
+    pub fn is_frozen(&self, t: Timestamp) -> bool {
+        self.frozen_until.is_some_and(|until| t < until)
+    }
+
+    /// Returns whether SENDER is allowed to trigger a distribution
+    /// under the current claim policy.
+    pub fn may_distribute(&self, sender: &Addr) -> bool {
+        *sender == self.recipient
+            || match &self.claim_policy {
+                ClaimPolicy::Anyone => true,
+                ClaimPolicy::RecipientOnly => false,
+                ClaimPolicy::Operators(operators) => operators.contains(sender),
+            }
+    }
+
+    /// Returns the operators allowed to distribute on behalf of the
+    /// vest receiver. Empty unless the claim policy lists operators.
+    pub fn operators(&self) -> Vec<Addr> {
+        match &self.claim_policy {
+            ClaimPolicy::Operators(operators) => operators.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Builds the message paying AMOUNT to the vest receiver, either
+    /// locally or over IBC if a remote destination is configured. IBC
+    /// payouts are recorded as in flight, since they may be refunded.
+    pub fn payout_message(&mut self, amount: Uint128, t: Timestamp) -> Result<CosmosMsg, ContractError> {
+        match &self.ibc_payout {
+            None => Ok(self.denom.get_transfer_to_message(&self.recipient, amount)?),
+            Some(p) => {
+                let timeout = t.plus_seconds(p.timeout_seconds);
+                let msg = self.denom.get_ibc_transfer_message(
+                    &p.channel_id,
+                    &p.remote_address,
+                    amount,
+                    IbcTimeout::with_timestamp(timeout),
+                )?;
+                let until = timeout.plus_seconds(IBC_REFUND_GRACE_SECONDS);
+                self.ibc_in_flight += amount;
+                self.ibc_refund_until = max(self.ibc_refund_until, Some(until));
+                Ok(msg)
+            }
+        }
+    }
+
+    /// Fails if IBC distributions may still be refunded at `t`.
+    pub fn assert_no_ibc_refund(&self, t: Timestamp) -> Result<(), ContractError> {
+        match self.ibc_refund_until {
+            Some(until) if t < until && !self.ibc_in_flight.is_zero() => {
+                Err(ContractError::IbcRefundPending { until })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Scales down the curve after `t` so that it ends at NEW_TOTAL
+    /// instead of the current total. The curve is left untouched up
+    /// to `t`, so NEW_TOTAL may not be less than `vested(t)`. Returns
+    /// the number of tokens that will no longer vest.
+    pub fn reduce(&mut self, t: Timestamp, new_total: Uint128) -> Result<Uint128, ContractError> {
+        let total = self.total();
+        let vested = self.vested(t);
+        if new_total < vested || new_total >= total {
+            return Err(ContractError::InvalidReduction {
+                min: vested,
+                max: total,
+            });
+        }
+
+        let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
+        let steps = self
+            .steps_through(elapsed)
+            .into_iter()
+            .map(|(x, y)| match x.cmp(&elapsed) {
+                Ordering::Less => (x, y),
+                Ordering::Equal => (x, vested),
+                Ordering::Greater => (
+                    x,
+                    vested + (y - vested).multiply_ratio(new_total - vested, total - vested),
+                ),
+            })
+            .collect();
+        self.vested = piecewise_curve(steps)?;
+        Ok(total - new_total)
+    }
+
+    /// Appends SCHEDULE, vesting ADDITIONAL_TOTAL tokens over
+    /// DURATION_SECONDS, to the curve. The new segment starts at the
+    /// end of the curve, or at `t` if the curve has ended already.
+    pub fn extend(
+        &mut self,
+        t: Timestamp,
+        additional_total: Uint128,
+        duration_seconds: u64,
+        schedule: Schedule,
+    ) -> Result<(), ContractError> {
+        if additional_total.is_zero() {
+            return Err(ContractError::ZeroVest);
+        } else if duration_seconds == 0 {
+            return Err(ContractError::Instavest);
+        }
+        let segment = schedule.into_curve(additional_total, duration_seconds)?;
+
+        let total = self.total();
         let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
-        self.vested.value(elapsed)
+        let end = self.steps().last().map_or(0, |(x, _)| *x);
+        let (mut steps, offset) = if end < elapsed {
+            let mut steps = self.steps();
+            steps.push((elapsed, total));
+            (steps, elapsed)
+        } else {
+            // pin the amount vested now, see `piecewise_curve`
+            (self.steps_through(elapsed), end)
+        };
+
+        steps.extend(
+            curve_steps(&segment)
+                .into_iter()
+                .map(|(x, y)| (x + offset, y + total))
+                .filter(|(x, _)| *x > offset),
+        );
+
+        self.vested = piecewise_curve(steps)?;
+        self.top_up = additional_total;
+        self.status = Status::AwaitingTopUp;
+        Ok(())
+    }
+
+    /// Gets the vertices of the vesting curve in seconds since
+    /// `start_time`.
+    pub fn steps(&self) -> Vec<(u64, Uint128)> {
+        curve_steps(&self.vested)
+    }
+
+    /// Gets the vertices of the vesting curve with an additional
+    /// vertex at ELAPSED seconds since `start_time`.
+    fn steps_through(&self, elapsed: u64) -> Vec<(u64, Uint128)> {
+        let steps = self.steps();
+        let mut through: Vec<(u64, Uint128)> =
+            steps.iter().copied().filter(|(x, _)| *x < elapsed).collect();
+        through.push((elapsed, self.vested.value(elapsed)));
+        through.extend(steps.into_iter().filter(|(x, _)| *x > elapsed));
+        through
+    }
+
+    /// Gets the vertices of the vesting curve as absolute timestamps.
+    /// A pending cancellation cuts the curve off when it takes effect
+    /// and an unfunded extension flattens it at the funded amount.
+    /// After cancellation, the curve is a single constant point.
+    pub fn breakpoints(&self) -> Vec<VestingPoint> {
+        let cap = self.total() - self.top_up;
+        let end = self
+            .pending_cancel
+            .map(|c| c.seconds().saturating_sub(self.start_time.seconds()));
+
+        let mut points = vec![];
+        let mut prev: Option<(u64, Uint128)> = None;
+        for (x, y) in self.steps() {
+            let (x, y) = match end {
+                Some(end) if x > end => (end, self.vested.value(end)),
+                _ => (x, y),
+            };
+            // the curve flattens where it reaches the funded amount
+            if let Some((px, py)) = prev {
+                if py < cap && y > cap {
+                    let dx = (cap - py).multiply_ratio(x - px, y - py).u128() as u64;
+                    points.push((px + dx, cap));
+                }
+            }
+            points.push((x, min(y, cap)));
+            prev = Some((x, y));
+            if end.is_some_and(|end| x >= end) {
+                break;
+            }
+        }
+
+        points
+            .into_iter()
+            .map(|(x, vested)| VestingPoint {
+                time: self.start_time.plus_seconds(x),
+                vested,
+            })
+            .collect()
+    }
+
+    /// Gets the time vesting ends, i.e. the time of the last vertex of
+    /// the vesting curve or, once cancelled, the cancellation.
+    pub fn end_time(&self) -> Timestamp {
+        self.canceled_at.unwrap_or_else(|| {
+            self.breakpoints()
+                .last()
+                .map_or(self.start_time, |p| p.time)
+        })
+    }
+
+    /// Gets the time after which unclaimed tokens may be returned to
+    /// the Community Pool, if a claim deadline is configured. The
+    /// deadline runs from the end of the schedule or, once cancelled,
+    /// from the cancellation.
+    pub fn claim_deadline(&self) -> Option<Timestamp> {
+        self.claim_deadline_seconds
+            .map(|seconds| self.end_time().plus_seconds(seconds))
+    }
+
+    /// Checks the accounting invariants of the vest at `t` given the
+    /// contract's BALANCE and describes any violation:
+    ///
+    /// 1. `claimed <= vested(t) <= total`,
+    /// 2. `balance >= total - claimed` while funded.
+    pub fn invariant_violations(&self, t: Timestamp, balance: Uint128) -> Vec<String> {
+        let mut violations = vec![];
+        let total = self.total();
+        let vested = self.vested(t);
+        if self.claimed > vested {
+            violations.push(format!("claimed ({}) exceeds vested ({vested})", self.claimed));
+        }
+        if vested > total {
+            violations.push(format!("vested ({vested}) exceeds total ({total})"));
+        }
+        let owed = match self.status {
+            Status::Funded => Some(total.saturating_sub(self.claimed)),
+            Status::AwaitingTopUp => Some((total - self.top_up).saturating_sub(self.claimed)),
+            Status::Completed => Some(Uint128::zero()),
+            Status::Unfunded | Status::Canceled | Status::Closed => None,
+        };
+        if let Some(owed) = owed {
+            if balance < owed {
+                violations.push(format!("balance ({balance}) is below unclaimed ({owed})"));
+            }
+        }
+        violations
+    }
+
+    /// Gets the releases of the vest, i.e. the breakpoints at which
+    /// the vested amount is higher than at the previous breakpoint,
+    /// together with the increase.
+    pub fn releases(&self) -> Vec<Release> {
+        self.breakpoints()
+            .windows(2)
+            .filter(|w| w[1].vested > w[0].vested)
+            .map(|w| Release {
+                time: w[1].time,
+                amount: w[1].vested - w[0].vested,
+            })
+            .collect()
+    }
+
+    /// Gets the next release after AFTER, with the amount vesting
+    /// between AFTER and the release.
+    pub fn next_release(&self, after: Timestamp) -> Option<Release> {
+        self.releases()
+            .into_iter()
+            .find(|r| r.time > after)
+            .map(|r| Release {
+                time: r.time,
+                amount: self.vested(r.time) - self.vested(after),
+            })
+    }
+
+    /// Lists up to LIMIT releases after START_AFTER.
+    pub fn release_calendar(&self, start_after: Option<Timestamp>, limit: Option<u32>) -> Vec<Release> {
+        let limit = limit.unwrap_or(DEFAULT_CALENDAR_LIMIT).min(MAX_CALENDAR_LIMIT) as usize;
+        self.releases()
+            .into_iter()
+            .filter(|r| start_after.is_none_or(|after| r.time > after))
+            .take(limit)
+            .collect()
+    }
+
+    /// Samples `vested(t)` at POINTS evenly spaced times between FROM
+    /// and TO, both inclusive.
+    pub fn timeline(
+        &self,
+        from: Timestamp,
+        to: Timestamp,
+        points: u32,
+    ) -> StdResult<Vec<VestingPoint>> {
+        if to <= from || !(2..=MAX_TIMELINE_POINTS).contains(&points) {
+            return Err(StdError::generic_err(format!(
+                "timeline requires from < to and 2 to {MAX_TIMELINE_POINTS} points"
+            )));
+        }
+        let span = (to.nanos() - from.nanos()) as u128;
+        Ok((0..points)
+            .map(|i| {
+                let offset = span * i as u128 / (points - 1) as u128;
+                let time = from.plus_nanos(offset as u64);
+                VestingPoint {
+                    time,
+                    vested: self.vested(time),
+                }
+            })
+            .collect())
     }
 
     /// Cancels the current vest. No additional tokens will vest after `t`.
@@ -238,6 +1082,9 @@ impl Vest {
 
         self.status = Status::Canceled;
         self.vested = Curve::Constant { y: self.vested(t) };
+        self.pending_cancel = None;
+        self.canceled_at = Some(t);
+        self.top_up = Uint128::zero();
     }
 
     /// Gets the duration of the vest. For constant curves, `None` is
@@ -254,6 +1101,68 @@ impl Vest {
     }
 }
 
+/// Gets the vertices of CURVE.
+fn curve_steps(curve: &Curve) -> Vec<(u64, Uint128)> {
+    match curve {
+        Curve::Constant { y } => vec![(0, *y)],
+        Curve::SaturatingLinear(SaturatingLinear {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }) => vec![(*min_x, *min_y), (*max_x, *max_y)],
+        Curve::PiecewiseLinear(PiecewiseLinear { steps }) => steps.clone(),
+    }
+}
+
+/// Builds a piecewise linear curve from STEPS. Constant and saturating
+/// linear curves have a vertex at x = 0, which piecewise linear curves
+/// do not allow. It is moved to x = 1, which delays the first segment
+/// by a second, or dropped if the next vertex is at x = 1. Callers add
+/// a vertex at the current time, so that only the past changes, or,
+/// before the start, the first second vests nothing.
+fn piecewise_curve(mut steps: Vec<(u64, Uint128)>) -> Result<Curve, ContractError> {
+    if steps.first().is_some_and(|(x, _)| *x == 0) {
+        if steps.get(1).is_some_and(|(x, _)| *x == 1) {
+            steps.remove(0);
+        } else {
+            steps[0].0 = 1;
+        }
+    }
+    let curve = Curve::PiecewiseLinear(PiecewiseLinear { steps });
+    curve.validate_monotonic_increasing()?;
+    Ok(curve)
+}
+
+impl IbcPayout {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if !self.channel_id.starts_with("channel-")
+            || self.remote_address.is_empty()
+            || self.timeout_seconds == 0
+        {
+            Err(ContractError::InvalidIbcPayout)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl UncheckedClaimPolicy {
+    /// Validates the operator addresses of the policy.
+    pub fn into_checked(self, api: &dyn Api) -> StdResult<ClaimPolicy> {
+        Ok(match self {
+            Self::Anyone => ClaimPolicy::Anyone,
+            Self::RecipientOnly => ClaimPolicy::RecipientOnly,
+            Self::Operators(operators) => ClaimPolicy::Operators(
+                operators
+                    .iter()
+                    .map(|o| api.addr_validate(o))
+                    .collect::<StdResult<_>>()?,
+            ),
+        })
+    }
+}
+
 impl Schedule {
     /// The vesting schedule tracks vested(t), so for a curve to be
     /// valid:
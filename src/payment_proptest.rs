@@ -0,0 +1,142 @@
+use cosmwasm_std::{
+    testing::mock_dependencies, BankMsg, CosmosMsg, DistributionMsg, Timestamp, Uint128,
+};
+use proptest::prelude::*;
+
+use crate::payment::{Payment, Schedule, Vest, VestInit};
+
+/// Generates a valid schedule together with its total and duration.
+/// Piecewise schedules have random steps and plateaus.
+fn schedule() -> impl Strategy<Value = (Schedule, Uint128, u64)> {
+    prop_oneof![
+        (1u128..1_000_000_000_000_000, 1u64..100_000_000).prop_map(|(total, duration)| (
+            Schedule::SaturatingLinear,
+            Uint128::new(total),
+            duration
+        )),
+        prop::collection::vec((1u64..1_000_000, 0u128..1_000_000_000_000), 1..20).prop_map(
+            |increments| {
+                let mut steps = vec![(1, Uint128::zero())];
+                let (mut x, mut y) = (1, 0);
+                for (dx, dy) in increments {
+                    x += dx;
+                    y += dy;
+                    steps.push((x, Uint128::new(y)));
+                }
+                // the total must be non-zero
+                steps.push((x + 1, Uint128::new(y + 1)));
+                (Schedule::PiecewiseLinear(steps), Uint128::new(y + 1), x + 1)
+            }
+        ),
+    ]
+}
+
+/// Sums the amounts sent to the vestee and to the Community Pool.
+fn payouts(msgs: &[CosmosMsg]) -> (Uint128, Uint128) {
+    msgs.iter()
+        .fold((Uint128::zero(), Uint128::zero()), |(vestee, pool), msg| match msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => (vestee + amount[0].amount, pool),
+            CosmosMsg::Distribution(DistributionMsg::FundCommunityPool { amount }) => {
+                (vestee, pool + amount[0].amount)
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        })
+}
+
+proptest! {
+    #[test]
+    fn vested_is_monotonic(
+        (schedule, total, duration) in schedule(),
+        times in prop::collection::vec(any::<u64>(), 1..50),
+    ) {
+        let vest = Vest::new(VestInit {
+            total,
+            schedule,
+            duration_seconds: duration,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut times: Vec<u64> = times.into_iter().map(|t| t % (duration * 2)).collect();
+        times.sort_unstable();
+        let vested: Vec<Uint128> = times
+            .into_iter()
+            .map(|t| vest.vested(Timestamp::from_seconds(t)))
+            .collect();
+
+        prop_assert!(vested.windows(2).all(|w| w[0] <= w[1]));
+        prop_assert!(vested.iter().all(|v| *v <= total));
+        prop_assert_eq!(vest.vested(Timestamp::from_seconds(0)), Uint128::zero());
+        prop_assert_eq!(vest.vested(Timestamp::from_seconds(duration)), total);
+        prop_assert_eq!(vest.total(), total);
+    }
+
+    #[test]
+    fn payouts_conserve_funds(
+        (schedule, total, duration) in schedule(),
+        surplus in 0u128..1_000_000,
+        ops in prop::collection::vec((any::<u64>(), 0u128..=100), 0..20),
+        cancel_at in any::<u64>(),
+    ) {
+        let storage = &mut mock_dependencies().storage;
+        let payment = Payment::new("vesting");
+        payment
+            .initialize(
+                storage,
+                VestInit {
+                    total,
+                    schedule,
+                    duration_seconds: duration,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        payment.set_funded(storage).unwrap();
+
+        let funded = total + Uint128::new(surplus);
+        let mut balance = funded;
+        let mut to_vestee = Uint128::zero();
+
+        // distribute random shares of the distributable amount
+        let mut ops: Vec<(u64, u128)> = ops
+            .into_iter()
+            .map(|(t, percent)| (t % (duration * 2), percent))
+            .collect();
+        ops.sort_unstable();
+        for &(t, percent) in &ops {
+            let t = Timestamp::from_seconds(t);
+            let vest = payment.get_vest(storage).unwrap();
+            let distributable = payment.distributable(storage, &vest, t).unwrap();
+            let request = distributable.multiply_ratio(percent, 100u128);
+            match payment.distribute(storage, t, Some(request)) {
+                Ok(msg) => {
+                    prop_assert_eq!(payouts(&[msg]), (request, Uint128::zero()));
+                    to_vestee += request;
+                    balance -= request;
+                }
+                Err(_) => prop_assert!(request.is_zero()),
+            }
+
+            // over-claims fail without changing the state
+            let vest = payment.get_vest(storage).unwrap();
+            let over = payment.distributable(storage, &vest, t).unwrap() + Uint128::one();
+            prop_assert!(payment.distribute(storage, t, Some(over)).is_err());
+            prop_assert_eq!(&payment.get_vest(storage).unwrap(), &vest);
+            prop_assert_eq!(vest.invariant_violations(t, balance), Vec::<String>::new());
+            prop_assert_eq!(vest.claimed, to_vestee);
+        }
+
+        // cancel after the last distribution
+        let last = ops.last().map_or(0, |(t, _)| *t);
+        let t = Timestamp::from_seconds(last + cancel_at % (duration * 2));
+        let vested = payment.get_vest(storage).unwrap().vested(t);
+        let msgs = payment.cancel(storage, t, balance).unwrap();
+        let (vestee, pool) = payouts(&msgs);
+
+        prop_assert_eq!(to_vestee + vestee, vested);
+        prop_assert_eq!(to_vestee + vestee + pool, funded);
+        let vest = payment.get_vest(storage).unwrap();
+        prop_assert_eq!(vest.total(), vested);
+        prop_assert_eq!(vest.debt, Uint128::zero());
+    }
+}
@@ -1,5 +1,5 @@
 #[cfg(test)]
-use cosmwasm_std::{testing::mock_dependencies, Addr, BankMsg, Coin, CosmosMsg, DistributionMsg, Timestamp, Uint128};
+use cosmwasm_std::{testing::mock_dependencies, Addr, BankMsg, Coin, CosmosMsg, Decimal, DistributionMsg, IbcMsg, IbcTimeout, Timestamp, Uint128};
 
 #[cfg(test)]
 use crate::denom::CheckedDenom;
@@ -10,7 +10,10 @@ use wynd_utils::CurveError;
 #[cfg(test)]
 use crate::{
     error::ContractError,
-    payment::{Payment, Schedule, Vest, VestInit},
+    payment::{
+        ClaimPolicy, IbcPayout, KeeperConfig, Payment, Release, Schedule, Status, Vest, VestInit,
+        VestingPoint, IBC_REFUND_GRACE_SECONDS,
+    },
 };
 
 #[cfg(test)]
@@ -25,6 +28,10 @@ impl Default for VestInit {
             recipient: Addr::unchecked("recv"),
             title: "title".to_string(),
             description: Some("desc".to_string()),
+            keeper: None,
+            cancel_notice_seconds: 0,
+            guardian: None,
+            claim_deadline_seconds: None,
         }
     }
 }
@@ -87,6 +94,9 @@ fn test_distribute_half_way() {
             claimable: Uint128::new(50_000_000)
         }
     );
+
+    // a rejected request does not change the claimed amount
+    assert_eq!(payment.get_vest(storage).unwrap().claimed, Uint128::zero());
 }
 
 #[test]
@@ -181,6 +191,7 @@ fn test_cancellation() {
         recipient: Addr::unchecked("recv"),
         title: "t".to_string(),
         description: Some("d".to_string()),
+        ..Default::default()
     };
     let payment = Payment::new("vesting");
 
@@ -227,6 +238,7 @@ fn test_cancellation_no_zero_payments() {
         recipient: Addr::unchecked("recv"),
         title: "t".to_string(),
         description: Some("d".to_string()),
+        ..Default::default()
     };
     let payment = Payment::new("vesting");
 
@@ -242,6 +254,7 @@ fn test_cancellation_no_zero_payments() {
     payment.distribute(storage, time, None).unwrap();
 
     assert_eq!(payment.get_vest(storage).unwrap().claimed, Uint128::new(100));
+    assert_eq!(payment.get_vest(storage).unwrap().status, Status::Completed);
 
     // cancel the payment after schedule - contract balance 0 tokens left (not overfunded)
     // -> 100 are claimed by the vestee -> 0 to be sent to the vestee
@@ -266,6 +279,7 @@ fn test_cancellation_contract_overfunding() {
         recipient: Addr::unchecked("recv"),
         title: "t".to_string(),
         description: Some("d".to_string()),
+        ..Default::default()
     };
     let payment = Payment::new("vesting");
 
@@ -295,6 +309,51 @@ fn test_cancellation_contract_overfunding() {
 
 }
 
+#[test]
+fn test_cancellation_shortfall() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    // the contract was slashed and only holds 30M of the 50M vested
+    let time = Timestamp::from_seconds(50);
+    let resp = payment.cancel(storage, time, Uint128::new(30_000_000)).unwrap();
+    assert_eq!(
+        resp,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: "recv".to_string(),
+            amount: vec![Coin::new(30_000_000u128, "native")],
+        })]
+    );
+
+    // the rest is owed to the vestee and blocks closing
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.debt, Uint128::new(20_000_000));
+    assert_eq!(
+        payment.close(storage, time, Uint128::zero()).unwrap_err(),
+        ContractError::Debt {
+            debt: Uint128::new(20_000_000)
+        }
+    );
+
+    // and may be distributed once the contract holds it again
+    let time = time.plus_seconds(10);
+    assert_eq!(
+        payment.distributable(storage, &vest, time).unwrap(),
+        Uint128::new(20_000_000)
+    );
+    payment
+        .distribute(storage, time, Some(Uint128::new(15_000_000)))
+        .unwrap();
+    payment.distribute(storage, time, None).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.debt, Uint128::zero());
+    assert_eq!(payment.distributable(storage, &vest, time).unwrap(), Uint128::zero());
+    assert!(payment.close(storage, time, Uint128::zero()).unwrap().is_empty());
+}
+
 #[test]
 fn test_piecewise_linear() {
     let storage = &mut mock_dependencies().storage;
@@ -357,4 +416,938 @@ fn test_piecewise_linear() {
             .unwrap(),
         Uint128::new(8)
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_distribute_ibc_payout() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    // invalid destinations are rejected
+    let err = payment
+        .set_ibc_payout(
+            storage,
+            Some(IbcPayout {
+                channel_id: "channel-1".to_string(),
+                remote_address: "osmo1recv".to_string(),
+                timeout_seconds: 0,
+            }),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidIbcPayout);
+
+    payment
+        .set_ibc_payout(
+            storage,
+            Some(IbcPayout {
+                channel_id: "channel-1".to_string(),
+                remote_address: "osmo1recv".to_string(),
+                timeout_seconds: 600,
+            }),
+        )
+        .unwrap();
+
+    let time = Timestamp::from_seconds(50);
+    let msg = payment.distribute(storage, time, None).unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id: "channel-1".to_string(),
+            to_address: "osmo1recv".to_string(),
+            amount: Coin::new(50_000_000u128, "native"),
+            timeout: IbcTimeout::with_timestamp(time.plus_seconds(600)),
+        })
+    );
+    assert_eq!(payment.get_vest(storage).unwrap().claimed, Uint128::new(50_000_000));
+}
+
+#[test]
+fn test_claim_ibc_refund() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .set_ibc_payout(
+            storage,
+            Some(IbcPayout {
+                channel_id: "channel-1".to_string(),
+                remote_address: "osmo1recv".to_string(),
+                timeout_seconds: 600,
+            }),
+        )
+        .unwrap();
+
+    let time = Timestamp::from_seconds(50);
+    payment.distribute(storage, time, None).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.ibc_in_flight, Uint128::new(50_000_000));
+
+    // the balance held for the vestee is not a refund
+    assert_eq!(
+        payment
+            .claim_ibc_refund(storage, Uint128::new(50_000_000))
+            .unwrap_err(),
+        ContractError::NoIbcRefund
+    );
+
+    // the transfer timed out and 30M of it were refunded
+    let msg = payment
+        .claim_ibc_refund(storage, Uint128::new(80_000_000))
+        .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "recv".to_string(),
+            amount: vec![Coin::new(30_000_000u128, "native")],
+        })
+    );
+    assert_eq!(
+        payment.get_vest(storage).unwrap().ibc_in_flight,
+        Uint128::new(20_000_000)
+    );
+
+    // refunds can not exceed what was sent over IBC
+    payment.set_ibc_payout(storage, None).unwrap();
+    let time = Timestamp::from_seconds(100);
+    payment.distribute(storage, time, None).unwrap();
+    let msg = payment
+        .claim_ibc_refund(storage, Uint128::new(50_000_000))
+        .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "recv".to_string(),
+            amount: vec![Coin::new(20_000_000u128, "native")],
+        })
+    );
+
+    // with every IBC distribution refunded, the vest may be closed
+    // before the refund window ends
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.ibc_in_flight, Uint128::zero());
+    assert_eq!(
+        vest.ibc_refund_until,
+        Some(Timestamp::from_seconds(650 + IBC_REFUND_GRACE_SECONDS))
+    );
+    payment.close(storage, time, Uint128::zero()).unwrap();
+}
+
+#[test]
+fn test_ibc_refund_blocks_close() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .set_ibc_payout(
+            storage,
+            Some(IbcPayout {
+                channel_id: "channel-1".to_string(),
+                remote_address: "osmo1recv".to_string(),
+                timeout_seconds: 600,
+            }),
+        )
+        .unwrap();
+
+    let time = Timestamp::from_seconds(100);
+    payment.distribute(storage, time, None).unwrap();
+    assert_eq!(payment.get_vest(storage).unwrap().status, Status::Completed);
+
+    let until = time.plus_seconds(600 + IBC_REFUND_GRACE_SECONDS);
+    assert_eq!(
+        payment
+            .close(storage, until.minus_seconds(1), Uint128::zero())
+            .unwrap_err(),
+        ContractError::IbcRefundPending { until }
+    );
+    payment.close(storage, until, Uint128::zero()).unwrap();
+}
+
+#[test]
+fn test_keeper_distribute() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+    let keeper = Addr::unchecked("keeper");
+
+    let init = VestInit {
+        keeper: Some(KeeperConfig {
+            tip_rate: Decimal::percent(1),
+            max_tip: Uint128::new(100_000),
+            min_interval_seconds: 20,
+        }),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    // 1% of 10_000_000 is below the cap
+    let msgs = payment
+        .keeper_distribute(storage, Timestamp::from_seconds(10), None, &keeper)
+        .unwrap();
+    assert_eq!(
+        msgs,
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recv".to_string(),
+                amount: vec![Coin::new(9_900_000u128, "native")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper".to_string(),
+                amount: vec![Coin::new(100_000u128, "native")],
+            }),
+        ]
+    );
+
+    // too early for the next keeper distribution
+    let err = payment
+        .keeper_distribute(storage, Timestamp::from_seconds(29), None, &keeper)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::KeeperTooEarly {
+            next: Timestamp::from_seconds(30)
+        }
+    );
+
+    // 1% of 40_000_000 is capped
+    let msgs = payment
+        .keeper_distribute(storage, Timestamp::from_seconds(50), None, &keeper)
+        .unwrap();
+    assert_eq!(
+        msgs[1],
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: vec![Coin::new(100_000u128, "native")],
+        })
+    );
+    assert_eq!(payment.get_vest(storage).unwrap().claimed, Uint128::new(50_000_000));
+}
+
+#[test]
+fn test_keeper_tip_validation() {
+    let init = VestInit {
+        keeper: Some(KeeperConfig {
+            tip_rate: Decimal::percent(11),
+            max_tip: Uint128::new(100),
+            min_interval_seconds: 0,
+        }),
+        ..Default::default()
+    };
+    assert_eq!(Vest::new(init), Err(ContractError::KeeperTipTooHigh));
+}
+
+#[test]
+fn test_claim_policy() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+    let recv = Addr::unchecked("recv");
+    let operator = Addr::unchecked("operator");
+    let other = Addr::unchecked("other");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert!(vest.may_distribute(&other));
+    assert!(vest.operators().is_empty());
+
+    payment
+        .set_claim_policy(storage, ClaimPolicy::RecipientOnly)
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert!(vest.may_distribute(&recv));
+    assert!(!vest.may_distribute(&other));
+
+    payment
+        .set_claim_policy(storage, ClaimPolicy::Operators(vec![operator.clone()]))
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert!(vest.may_distribute(&recv));
+    assert!(vest.may_distribute(&operator));
+    assert!(!vest.may_distribute(&other));
+    assert_eq!(vest.operators(), vec![operator]);
+}
+
+#[test]
+fn test_cancellation_notice_period() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        cancel_notice_seconds: 20,
+        denom: CheckedDenom::Native("uluna".to_string()),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let effective = payment
+        .schedule_cancel(storage, Timestamp::from_seconds(30))
+        .unwrap();
+    assert_eq!(effective, Timestamp::from_seconds(50));
+    assert_eq!(
+        payment
+            .schedule_cancel(storage, Timestamp::from_seconds(31))
+            .unwrap_err(),
+        ContractError::CancelPending { effective }
+    );
+
+    // vesting continues during the notice period, but not after
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(40)), Uint128::new(40));
+    assert_eq!(vest.vested(Timestamp::from_seconds(80)), Uint128::new(50));
+
+    assert_eq!(
+        payment
+            .finalize_cancel(storage, Timestamp::from_seconds(49), Uint128::new(100))
+            .unwrap_err(),
+        ContractError::CancelNoticeActive { effective }
+    );
+
+    let resp = payment
+        .finalize_cancel(storage, Timestamp::from_seconds(80), Uint128::new(100))
+        .unwrap();
+    assert_eq!(
+        resp,
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recv".to_string(),
+                amount: vec![Coin::new(50u128, "uluna")],
+            }),
+            CosmosMsg::Distribution(DistributionMsg::FundCommunityPool {
+                amount: vec![Coin::new(50u128, "uluna")],
+            }),
+        ]
+    );
+    assert_eq!(payment.get_vest(storage).unwrap().total(), Uint128::new(50));
+}
+
+#[test]
+fn test_revoke_cancellation() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        cancel_notice_seconds: 20,
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    assert_eq!(
+        payment.revoke_cancel(storage).unwrap_err(),
+        ContractError::NoPendingCancel
+    );
+
+    payment
+        .schedule_cancel(storage, Timestamp::from_seconds(10))
+        .unwrap();
+    payment.revoke_cancel(storage).unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.pending_cancel, None);
+    assert_eq!(vest.vested(Timestamp::from_seconds(100)), vest.total());
+}
+
+#[test]
+fn test_reduce() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        schedule: Schedule::PiecewiseLinear(vec![
+            (1, Uint128::zero()),
+            (41, Uint128::new(40)),
+            (101, Uint128::new(100)),
+        ]),
+        denom: CheckedDenom::Native("uluna".to_string()),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let time = Timestamp::from_seconds(21);
+
+    // can't reduce below what has vested already
+    assert_eq!(
+        payment.reduce(storage, time, Uint128::new(19)).unwrap_err(),
+        ContractError::InvalidReduction {
+            min: Uint128::new(20),
+            max: Uint128::new(100)
+        }
+    );
+
+    let msg = payment.reduce(storage, time, Uint128::new(60)).unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Distribution(DistributionMsg::FundCommunityPool {
+            amount: vec![Coin::new(40u128, "uluna")],
+        })
+    );
+
+    // the future portion is halved, the past is unchanged
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.total(), Uint128::new(60));
+    assert_eq!(vest.vested(Timestamp::from_seconds(11)), Uint128::new(10));
+    assert_eq!(vest.vested(time), Uint128::new(20));
+    assert_eq!(vest.vested(Timestamp::from_seconds(41)), Uint128::new(30));
+    assert_eq!(vest.vested(Timestamp::from_seconds(101)), Uint128::new(60));
+    assert_eq!(vest.status, Status::Funded);
+}
+
+#[test]
+fn test_reduce_saturating_linear() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let time = Timestamp::from_seconds(20);
+    payment.reduce(storage, time, Uint128::new(60_000_000)).unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.total(), Uint128::new(60_000_000));
+    assert_eq!(vest.vested(time), Uint128::new(20_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(60)), Uint128::new(40_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(100)), Uint128::new(60_000_000));
+}
+
+#[test]
+fn test_reduce_before_start() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        start_time: Timestamp::from_seconds(100),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    payment
+        .reduce(storage, Timestamp::from_seconds(50), Uint128::new(50_000_000))
+        .unwrap();
+
+    // nothing vests before the start, then half of the original rate
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.total(), Uint128::new(50_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(100)), Uint128::zero());
+    assert_eq!(vest.vested(Timestamp::from_seconds(200)), Uint128::new(50_000_000));
+
+    // the same for a piecewise schedule starting at x = 1
+    let storage = &mut mock_dependencies().storage;
+    let init = VestInit {
+        start_time: Timestamp::from_seconds(100),
+        schedule: Schedule::PiecewiseLinear(vec![
+            (1, Uint128::zero()),
+            (101, Uint128::new(100_000_000)),
+        ]),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .reduce(storage, Timestamp::from_seconds(50), Uint128::new(50_000_000))
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(101)), Uint128::zero());
+    assert_eq!(vest.vested(Timestamp::from_seconds(151)), Uint128::new(25_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(201)), Uint128::new(50_000_000));
+}
+
+#[test]
+fn test_reduce_cancel_pending() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        cancel_notice_seconds: 20,
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    // a reduction during the notice period could lower the amount
+    // vested below what the cancellation pays out
+    let effective = payment
+        .schedule_cancel(storage, Timestamp::from_seconds(30))
+        .unwrap();
+    assert_eq!(
+        payment
+            .reduce(storage, Timestamp::from_seconds(40), Uint128::new(60_000_000))
+            .unwrap_err(),
+        ContractError::CancelPending { effective }
+    );
+
+    // once the cancellation is revoked the total can be reduced
+    payment.revoke_cancel(storage).unwrap();
+    payment
+        .reduce(storage, Timestamp::from_seconds(40), Uint128::new(60_000_000))
+        .unwrap();
+}
+
+#[test]
+fn test_extend() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .distribute(storage, Timestamp::from_seconds(60), None)
+        .unwrap();
+
+    // the schedule has ended, so the extension starts now
+    let vest = payment
+        .extend(
+            storage,
+            Timestamp::from_seconds(120),
+            Uint128::new(50),
+            50,
+            Schedule::SaturatingLinear,
+        )
+        .unwrap();
+    assert_eq!(vest.status, Status::AwaitingTopUp);
+    assert_eq!(vest.total(), Uint128::new(150));
+    assert_eq!(vest.claimed, Uint128::new(60));
+
+    // only the funded part vests until the top-up arrives
+    assert_eq!(vest.vested(Timestamp::from_seconds(145)), Uint128::new(100));
+    assert_eq!(
+        payment
+            .distributable(storage, &vest, Timestamp::from_seconds(145))
+            .unwrap(),
+        Uint128::new(40)
+    );
+    assert_eq!(
+        payment
+            .extend(
+                storage,
+                Timestamp::from_seconds(145),
+                Uint128::new(50),
+                50,
+                Schedule::SaturatingLinear,
+            )
+            .unwrap_err(),
+        ContractError::AwaitingTopUp
+    );
+
+    payment.set_funded(storage).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(145)), Uint128::new(125));
+    assert_eq!(vest.vested(Timestamp::from_seconds(200)), Uint128::new(150));
+}
+
+#[test]
+fn test_extend_saturating_linear() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .distribute(storage, Timestamp::from_seconds(50), None)
+        .unwrap();
+
+    // the extension is appended to the running schedule, which is
+    // unchanged up to now
+    let vest = payment
+        .extend(
+            storage,
+            Timestamp::from_seconds(50),
+            Uint128::new(50_000_000),
+            50,
+            Schedule::SaturatingLinear,
+        )
+        .unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(50)), Uint128::new(50_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(75)), Uint128::new(75_000_000));
+
+    payment.set_funded(storage).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(125)), Uint128::new(125_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(150)), Uint128::new(150_000_000));
+}
+
+#[test]
+fn test_extend_before_start() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        start_time: Timestamp::from_seconds(100),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let vest = payment
+        .extend(
+            storage,
+            Timestamp::from_seconds(50),
+            Uint128::new(100_000_000),
+            100,
+            Schedule::SaturatingLinear,
+        )
+        .unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(100)), Uint128::zero());
+    assert_eq!(vest.vested(Timestamp::from_seconds(200)), Uint128::new(100_000_000));
+
+    payment.set_funded(storage).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.vested(Timestamp::from_seconds(250)), Uint128::new(150_000_000));
+    assert_eq!(vest.vested(Timestamp::from_seconds(300)), Uint128::new(200_000_000));
+}
+
+#[test]
+fn test_freeze() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let until = payment
+        .freeze(storage, Timestamp::from_seconds(10), 20)
+        .unwrap();
+    assert_eq!(until, Timestamp::from_seconds(30));
+    assert!(payment.get_vest(storage).unwrap().is_frozen(Timestamp::from_seconds(29)));
+
+    assert_eq!(
+        payment
+            .distribute(storage, Timestamp::from_seconds(29), None)
+            .unwrap_err(),
+        ContractError::Frozen { until }
+    );
+
+    // the freeze expires on its own
+    payment
+        .distribute(storage, Timestamp::from_seconds(30), None)
+        .unwrap();
+
+    payment
+        .freeze(storage, Timestamp::from_seconds(40), 20)
+        .unwrap();
+    payment.unfreeze(storage).unwrap();
+    payment
+        .distribute(storage, Timestamp::from_seconds(40), None)
+        .unwrap();
+}
+
+#[test]
+fn test_breakpoints_and_timeline() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        start_time: Timestamp::from_seconds(1000),
+        cancel_notice_seconds: 10,
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let point = |t: u64, v: u128| VestingPoint {
+        time: Timestamp::from_seconds(t),
+        vested: Uint128::new(v),
+    };
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.breakpoints(), vec![point(1000, 0), point(1100, 100)]);
+    assert_eq!(
+        vest.timeline(Timestamp::from_seconds(1000), Timestamp::from_seconds(1200), 3)
+            .unwrap(),
+        vec![point(1000, 0), point(1100, 100), point(1200, 100)]
+    );
+    assert!(vest
+        .timeline(Timestamp::from_seconds(1000), Timestamp::from_seconds(1200), 1)
+        .is_err());
+
+    // a pending cancellation cuts the curve off
+    payment
+        .schedule_cancel(storage, Timestamp::from_seconds(1030))
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.breakpoints(), vec![point(1000, 0), point(1040, 40)]);
+
+    // after cancellation only the final amount remains
+    payment
+        .finalize_cancel(storage, Timestamp::from_seconds(1040), Uint128::new(100))
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.breakpoints(), vec![point(1000, 40)]);
+}
+
+#[test]
+fn test_next_release() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    // two monthly tranches as in the README
+    let init = VestInit {
+        total: Uint128::new(1000),
+        schedule: Schedule::PiecewiseLinear(vec![
+            (1, Uint128::zero()),
+            (2592000, Uint128::zero()),
+            (2592001, Uint128::new(500)),
+            (5184000, Uint128::new(500)),
+            (5184001, Uint128::new(1000)),
+        ]),
+        duration_seconds: 5184001,
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+
+    assert_eq!(
+        vest.next_release(Timestamp::from_seconds(100)),
+        Some(Release {
+            time: Timestamp::from_seconds(2592001),
+            amount: Uint128::new(500)
+        })
+    );
+    assert_eq!(
+        vest.next_release(Timestamp::from_seconds(2592001)),
+        Some(Release {
+            time: Timestamp::from_seconds(5184001),
+            amount: Uint128::new(500)
+        })
+    );
+    assert_eq!(vest.next_release(Timestamp::from_seconds(5184001)), None);
+}
+
+#[test]
+fn test_release_calendar() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(300),
+        schedule: Schedule::PiecewiseLinear(vec![
+            (1, Uint128::zero()),
+            (10, Uint128::zero()),
+            (11, Uint128::new(100)),
+            (20, Uint128::new(100)),
+            (21, Uint128::new(200)),
+            (30, Uint128::new(200)),
+            (31, Uint128::new(300)),
+        ]),
+        duration_seconds: 31,
+        denom: CheckedDenom::Native("uluna".to_string()),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let release = |t: u64, amount: u128| Release {
+        time: Timestamp::from_seconds(t),
+        amount: Uint128::new(amount),
+    };
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(
+        vest.release_calendar(None, None),
+        vec![release(11, 100), release(21, 100), release(31, 100)]
+    );
+    assert_eq!(
+        vest.release_calendar(Some(Timestamp::from_seconds(11)), Some(1)),
+        vec![release(21, 100)]
+    );
+
+    // nothing is released after cancellation
+    payment
+        .cancel(storage, Timestamp::from_seconds(15), Uint128::new(300))
+        .unwrap();
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.release_calendar(None, None), vec![]);
+    assert_eq!(vest.next_release(Timestamp::from_seconds(15)), None);
+}
+
+#[test]
+fn test_invariant_violations() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+
+    // balance is not checked before funding
+    let vest = payment.get_vest(storage).unwrap();
+    assert!(vest
+        .invariant_violations(Timestamp::from_seconds(50), Uint128::zero())
+        .is_empty());
+
+    payment.set_funded(storage).unwrap();
+    payment
+        .distribute(storage, Timestamp::from_seconds(50), None)
+        .unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert!(vest
+        .invariant_violations(Timestamp::from_seconds(50), Uint128::new(50))
+        .is_empty());
+    assert_eq!(
+        vest.invariant_violations(Timestamp::from_seconds(50), Uint128::new(49)),
+        vec!["balance (49) is below unclaimed (50)".to_string()]
+    );
+}
+
+#[test]
+fn test_complete_and_close() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        denom: CheckedDenom::Native("uluna".to_string()),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    payment
+        .distribute(storage, Timestamp::from_seconds(50), None)
+        .unwrap();
+    assert_eq!(
+        payment
+            .close(storage, Timestamp::from_seconds(50), Uint128::new(50))
+            .unwrap_err(),
+        ContractError::NotCompleted
+    );
+
+    // claiming the last token completes the vest
+    payment
+        .distribute(storage, Timestamp::from_seconds(100), None)
+        .unwrap();
+    assert_eq!(payment.get_vest(storage).unwrap().status, Status::Completed);
+
+    // dust is swept to the Community Pool
+    let msgs = payment
+        .close(storage, Timestamp::from_seconds(100), Uint128::new(3))
+        .unwrap();
+    assert_eq!(
+        msgs,
+        vec![CosmosMsg::Distribution(DistributionMsg::FundCommunityPool {
+            amount: vec![Coin::new(3u128, "uluna")],
+        })]
+    );
+    assert_eq!(payment.get_vest(storage).unwrap().status, Status::Closed);
+    assert_eq!(
+        payment
+            .close(storage, Timestamp::from_seconds(100), Uint128::zero())
+            .unwrap_err(),
+        ContractError::Closed
+    );
+}
+
+#[test]
+fn test_reduce_to_claimed_completes() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    payment.initialize(storage, VestInit::default()).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    let time = Timestamp::from_seconds(50);
+    payment.distribute(storage, time, None).unwrap();
+    payment
+        .reduce(storage, time, Uint128::new(50_000_000))
+        .unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.status, Status::Completed);
+    assert_eq!(
+        payment.distributable(storage, &vest, Timestamp::from_seconds(100)).unwrap(),
+        Uint128::zero()
+    );
+    payment.close(storage, time, Uint128::zero()).unwrap();
+}
+
+#[test]
+fn test_reclaim_unclaimed() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        denom: CheckedDenom::Native("uluna".to_string()),
+        claim_deadline_seconds: Some(50),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+    payment
+        .distribute(storage, Timestamp::from_seconds(40), None)
+        .unwrap();
+
+    let deadline = Timestamp::from_seconds(150);
+    assert_eq!(payment.get_vest(storage).unwrap().claim_deadline(), Some(deadline));
+    assert_eq!(
+        payment
+            .reclaim_unclaimed(storage, Timestamp::from_seconds(149), Uint128::new(60))
+            .unwrap_err(),
+        ContractError::ClaimDeadlineNotReached { deadline }
+    );
+
+    let msgs = payment
+        .reclaim_unclaimed(storage, deadline, Uint128::new(60))
+        .unwrap();
+    assert_eq!(
+        msgs,
+        vec![CosmosMsg::Distribution(DistributionMsg::FundCommunityPool {
+            amount: vec![Coin::new(60u128, "uluna")],
+        })]
+    );
+    assert_eq!(payment.get_vest(storage).unwrap().status, Status::Closed);
+}
+
+#[test]
+fn test_claim_deadline_after_cancel() {
+    let storage = &mut mock_dependencies().storage;
+    let payment = Payment::new("vesting");
+
+    let init = VestInit {
+        claim_deadline_seconds: Some(50),
+        ..Default::default()
+    };
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    // the vestee is owed tokens the balance does not cover
+    let time = Timestamp::from_seconds(60);
+    payment
+        .cancel(storage, time, Uint128::new(40_000_000))
+        .unwrap();
+
+    // the deadline runs from the cancellation, not the start
+    let deadline = Timestamp::from_seconds(110);
+    assert_eq!(
+        payment.get_vest(storage).unwrap().claim_deadline(),
+        Some(deadline)
+    );
+    assert_eq!(
+        payment
+            .reclaim_unclaimed(storage, time, Uint128::new(20_000_000))
+            .unwrap_err(),
+        ContractError::ClaimDeadlineNotReached { deadline }
+    );
+    payment
+        .reclaim_unclaimed(storage, deadline, Uint128::new(20_000_000))
+        .unwrap();
+}
@@ -1,3 +1,11 @@
+use cw_storage_plus::Item;
+
+use crate::history::History;
 use crate::payment::Payment;
 
 pub const PAYMENT: Payment = Payment::new("vesting");
+pub const HISTORY: History = History::new("history", "history__kind", "history_count");
+
+/// Whether the owner may hand over the contract to a successor, set
+/// at instantiation.
+pub const OWNERSHIP_TRANSFERABLE: Item<bool> = Item::new("ownership_transferable");
@@ -0,0 +1,2 @@
+mod suite;
+mod tests;
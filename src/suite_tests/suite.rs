@@ -0,0 +1,292 @@
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    coins, Addr, Api, BankMsg, Binary, BlockInfo, Coin, CustomMsg, CustomQuery,
+    DistributionMsg, Empty, Querier, Storage, Timestamp, Uint128,
+};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, ContractWrapper, CosmosRouter, Distribution,
+    Executor, FailingModule, Module, StakeKeeper, WasmKeeper,
+};
+
+use crate::contract::{execute, instantiate, query};
+use crate::denom::UncheckedDenom;
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::payment::{Schedule, Vest};
+
+pub const OWNER: &str = "owner";
+pub const RECIPIENT: &str = "recipient";
+pub const COMMUNITY_POOL: &str = "community_pool";
+pub const DENOM: &str = "uluna";
+
+/// The Community Pool's balance at genesis.
+const POOL_BALANCE: u128 = 1_000_000_000_000;
+
+/// A distribution module that moves `FundCommunityPool` funds to the
+/// `COMMUNITY_POOL` account, which is also where spends are paid
+/// from. cw-multi-test does not support `FundCommunityPool`.
+pub struct CommunityPool;
+
+impl Module for CommunityPool {
+    type ExecT = DistributionMsg;
+    type QueryT = Empty;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: DistributionMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match msg {
+            DistributionMsg::FundCommunityPool { amount } => router.execute(
+                api,
+                storage,
+                block,
+                sender,
+                BankMsg::Send {
+                    to_address: COMMUNITY_POOL.to_string(),
+                    amount,
+                }
+                .into(),
+            ),
+            other => bail!("unsupported distribution message: {other:?}"),
+        }
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        _request: Empty,
+    ) -> AnyResult<Binary> {
+        bail!("unsupported distribution query")
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Empty,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unsupported distribution sudo message")
+    }
+}
+
+impl Distribution for CommunityPool {}
+
+pub type SuiteApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    CommunityPool,
+>;
+
+pub struct SuiteBuilder {
+    pub instantiate: InstantiateMsg,
+}
+
+pub struct Suite {
+    pub app: SuiteApp,
+    pub contract: Addr,
+    pub total: Uint128,
+}
+
+impl Default for SuiteBuilder {
+    fn default() -> Self {
+        Self {
+            instantiate: InstantiateMsg {
+                owner: OWNER.to_string(),
+                recipient: RECIPIENT.to_string(),
+                title: "title".to_string(),
+                description: Some("description".to_string()),
+                total: Uint128::new(100_000_000),
+                denom: UncheckedDenom::Native(DENOM.to_string()),
+                schedule: Schedule::SaturatingLinear,
+                start_time: None,
+                vesting_duration_seconds: 100_000,
+                keeper: None,
+                cancel_notice_seconds: None,
+                guardian: None,
+                allow_ownership_transfer: None,
+                claim_deadline_seconds: None,
+            },
+        }
+    }
+}
+
+impl SuiteBuilder {
+    pub fn with_total(mut self, total: u128) -> Self {
+        self.instantiate.total = Uint128::new(total);
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.instantiate.schedule = schedule;
+        self
+    }
+
+    pub fn with_start_time(mut self, start_time: Timestamp) -> Self {
+        self.instantiate.start_time = Some(start_time);
+        self
+    }
+
+    pub fn with_vesting_duration(mut self, seconds: u64) -> Self {
+        self.instantiate.vesting_duration_seconds = seconds;
+        self
+    }
+
+    /// Instantiates the contract. Returns the app together with the
+    /// result of the instantiation.
+    pub fn try_build(self, funds: &[Coin]) -> (SuiteApp, AnyResult<Addr>) {
+        let mut app = AppBuilder::new()
+            .with_distribution(CommunityPool)
+            .build(|router, _api, storage| {
+                router
+                    .bank
+                    .init_balance(
+                        storage,
+                        &Addr::unchecked(COMMUNITY_POOL),
+                        coins(POOL_BALANCE, DENOM),
+                    )
+                    .unwrap();
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(OWNER), coins(1_000, DENOM))
+                    .unwrap();
+            });
+        let code_id =
+            app.store_code(Box::new(ContractWrapper::new(execute, instantiate, query)));
+        let contract = app.instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &self.instantiate,
+            funds,
+            "tc-vesting",
+            None,
+        );
+        (app, contract)
+    }
+
+    pub fn build(self) -> Suite {
+        let total = self.instantiate.total;
+        let (app, contract) = self.try_build(&[]);
+        Suite {
+            app,
+            contract: contract.unwrap(),
+            total,
+        }
+    }
+}
+
+/// Gets the contract error of a failed execution.
+pub fn contract_error(err: anyhow::Error) -> ContractError {
+    err.downcast()
+        .unwrap_or_else(|err| panic!("not a contract error: {err:?}"))
+}
+
+impl Suite {
+    pub fn time(&self) -> Timestamp {
+        self.app.block_info().time
+    }
+
+    /// Advances the block time by SECONDS.
+    pub fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|b| {
+            b.time = b.time.plus_seconds(seconds);
+            b.height += seconds / 5;
+        })
+    }
+
+    /// Simulates a Community Pool spend of AMOUNT to the contract.
+    pub fn spend(&mut self, amount: u128) {
+        self.app
+            .send_tokens(
+                Addr::unchecked(COMMUNITY_POOL),
+                self.contract.clone(),
+                &coins(amount, DENOM),
+            )
+            .unwrap();
+    }
+
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+    ) -> Result<AppResponse, ContractError> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.contract.clone(), &msg, &[])
+            .map_err(contract_error)
+    }
+
+    pub fn fund(&mut self, sender: &str) -> Result<AppResponse, ContractError> {
+        self.execute(sender, ExecuteMsg::Fund {})
+    }
+
+    pub fn distribute(
+        &mut self,
+        sender: &str,
+        amount: Option<u128>,
+    ) -> Result<AppResponse, ContractError> {
+        self.execute(
+            sender,
+            ExecuteMsg::Distribute {
+                amount: amount.map(Uint128::new),
+            },
+        )
+    }
+
+    pub fn cancel(&mut self, sender: &str) -> Result<AppResponse, ContractError> {
+        self.execute(sender, ExecuteMsg::Cancel {})
+    }
+
+    pub fn query_vest(&self) -> Vest {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.contract, &QueryMsg::Info {})
+            .unwrap()
+    }
+
+    pub fn query_distributable(&self) -> u128 {
+        self.app
+            .wrap()
+            .query_wasm_smart::<Uint128>(&self.contract, &QueryMsg::Distributable { t: None })
+            .unwrap()
+            .u128()
+    }
+
+    pub fn balance(&self, addr: impl Into<String>) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(addr, DENOM)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    /// The amount the Community Pool has spent on the payment, net of
+    /// returned funds.
+    pub fn pool_spent(&self) -> u128 {
+        POOL_BALANCE - self.balance(COMMUNITY_POOL)
+    }
+}
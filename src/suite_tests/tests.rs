@@ -0,0 +1,246 @@
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{coins, Coin, Uint128};
+use cw_ownable::OwnershipError;
+use cw_utils::PaymentError;
+
+use crate::error::ContractError;
+use crate::msg::ExecuteMsg;
+use crate::payment::{Schedule, Status};
+
+use super::suite::{contract_error, SuiteBuilder, DENOM, OWNER, RECIPIENT};
+
+#[test]
+fn test_instantiate_validation() {
+    let err = |builder: SuiteBuilder, funds: &[Coin]| {
+        contract_error(builder.try_build(funds).1.unwrap_err())
+    };
+
+    assert_eq!(err(SuiteBuilder::default().with_total(0), &[]), ContractError::ZeroVest);
+    assert_eq!(
+        err(SuiteBuilder::default().with_vesting_duration(0), &[]),
+        ContractError::Instavest
+    );
+    assert_eq!(
+        err(SuiteBuilder::default(), &coins(10, DENOM)),
+        ContractError::PaymentError(PaymentError::NonPayable {})
+    );
+
+    // A schedule that ended before instantiation is a plain transfer.
+    let now = mock_env().block.time;
+    assert_eq!(
+        err(
+            SuiteBuilder::default()
+                .with_start_time(now.minus_seconds(100))
+                .with_vesting_duration(100),
+            &[]
+        ),
+        ContractError::Instavest
+    );
+
+    // The schedule must cover the total.
+    assert_eq!(
+        err(
+            SuiteBuilder::default().with_schedule(Schedule::PiecewiseLinear(vec![
+                (1, Uint128::zero()),
+                (2, Uint128::new(50_000_000)),
+            ])),
+            &[]
+        ),
+        ContractError::VestRange {
+            min: Uint128::zero(),
+            max: Uint128::new(50_000_000)
+        }
+    );
+}
+
+#[test]
+fn test_fund_from_community_pool() {
+    let mut suite = SuiteBuilder::default().build();
+    assert_eq!(suite.query_vest().status, Status::Unfunded);
+
+    // nothing has been spent yet
+    assert_eq!(
+        suite.fund("anyone").unwrap_err(),
+        ContractError::WrongFundAmount {
+            sent: Uint128::zero(),
+            expected: suite.total
+        }
+    );
+
+    suite.spend(suite.total.u128() / 2);
+    assert_eq!(
+        suite.fund("anyone").unwrap_err(),
+        ContractError::WrongFundAmount {
+            sent: suite.total / Uint128::new(2),
+            expected: suite.total
+        }
+    );
+
+    suite.spend(suite.total.u128() / 2);
+    suite.fund("anyone").unwrap();
+    assert_eq!(suite.query_vest().status, Status::Funded);
+    assert_eq!(suite.fund("anyone").unwrap_err(), ContractError::Funded);
+}
+
+#[test]
+fn test_distribute_over_time() {
+    let mut suite = SuiteBuilder::default().build();
+
+    // nothing vests before the payment is funded
+    suite.advance(10_000);
+    assert_eq!(suite.query_distributable(), 0);
+
+    suite.spend(suite.total.u128());
+    suite.fund(OWNER).unwrap();
+    assert_eq!(suite.query_distributable(), 10_000_000);
+
+    suite.advance(40_000);
+    assert_eq!(
+        suite.distribute("someone", Some(60_000_000)).unwrap_err(),
+        ContractError::InvalidWithdrawal {
+            request: Uint128::new(60_000_000),
+            claimable: Uint128::new(50_000_000),
+        }
+    );
+    suite.distribute(RECIPIENT, Some(20_000_000)).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 20_000_000);
+    assert_eq!(suite.query_distributable(), 30_000_000);
+
+    // anyone may distribute on behalf of the recipient
+    suite.distribute("someone", None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 50_000_000);
+    assert_eq!(suite.balance("someone"), 0);
+
+    suite.advance(60_000);
+    suite.distribute(RECIPIENT, None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 100_000_000);
+    assert_eq!(suite.balance(suite.contract.clone()), 0);
+    assert_eq!(suite.query_vest().status, Status::Completed);
+    assert_eq!(suite.pool_spent(), 100_000_000);
+}
+
+#[test]
+fn test_cancel_payouts() {
+    let mut suite = SuiteBuilder::default().build();
+    suite.spend(suite.total.u128());
+    suite.fund(OWNER).unwrap();
+
+    suite.advance(25_000);
+    suite.distribute(RECIPIENT, Some(10_000_000)).unwrap();
+
+    assert_eq!(
+        suite.cancel(RECIPIENT).unwrap_err(),
+        ContractError::Ownable(OwnershipError::NotOwner)
+    );
+    suite.cancel(OWNER).unwrap();
+
+    // vested but unclaimed tokens go to the recipient, the rest back
+    // to the Community Pool
+    assert_eq!(suite.balance(RECIPIENT), 25_000_000);
+    assert_eq!(suite.pool_spent(), 25_000_000);
+    assert_eq!(suite.balance(suite.contract.clone()), 0);
+    assert_eq!(suite.query_vest().status, Status::Canceled);
+
+    // nothing vests after cancellation
+    suite.advance(25_000);
+    assert_eq!(suite.query_distributable(), 0);
+    assert_eq!(suite.cancel(OWNER).unwrap_err(), ContractError::Cancelled);
+}
+
+#[test]
+fn test_overfunding() {
+    let mut suite = SuiteBuilder::default().build();
+    suite.spend(suite.total.u128() + 5_000_000);
+    suite.fund(OWNER).unwrap();
+
+    // the surplus does not vest
+    suite.advance(100_000);
+    suite.distribute(RECIPIENT, None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 100_000_000);
+    assert_eq!(suite.balance(suite.contract.clone()), 5_000_000);
+
+    // and is returned to the Community Pool on close
+    suite.execute("anyone", ExecuteMsg::Close {}).unwrap();
+    assert_eq!(suite.balance(suite.contract.clone()), 0);
+    assert_eq!(suite.pool_spent(), 100_000_000);
+}
+
+#[test]
+fn test_overfunded_cancel() {
+    let mut suite = SuiteBuilder::default().build();
+    suite.spend(suite.total.u128() + 5_000_000);
+    suite.fund(OWNER).unwrap();
+
+    suite.advance(50_000);
+    suite.cancel(OWNER).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 50_000_000);
+    assert_eq!(suite.pool_spent(), 50_000_000);
+}
+
+#[test]
+fn test_underfunded_cancel() {
+    let mut suite = SuiteBuilder::default().build();
+    suite.spend(30_000_000);
+
+    // the vestee is paid what there is and owed the rest
+    suite.advance(50_000);
+    let res = suite.cancel(OWNER).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 30_000_000);
+    assert_eq!(suite.query_vest().debt, Uint128::new(20_000_000));
+    assert!(res.events.iter().any(|e| e.ty == "wasm-tc_vesting_cancel"
+        && e.attributes.iter().any(|a| a.key == "debt" && a.value == "20000000")));
+    assert_eq!(
+        suite.execute("anyone", ExecuteMsg::Close {}).unwrap_err(),
+        ContractError::Debt {
+            debt: Uint128::new(20_000_000)
+        }
+    );
+
+    // a later spend settles the debt
+    suite.spend(20_000_000);
+    suite.distribute(RECIPIENT, None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 50_000_000);
+    assert_eq!(suite.query_vest().debt, Uint128::zero());
+    suite.execute("anyone", ExecuteMsg::Close {}).unwrap();
+    assert_eq!(suite.query_vest().status, Status::Closed);
+}
+
+#[test]
+fn test_start_time_in_the_past() {
+    // half of the schedule has passed at instantiation
+    let now = mock_env().block.time;
+    let mut suite = SuiteBuilder::default()
+        .with_start_time(now.minus_seconds(50_000))
+        .build();
+
+    suite.spend(suite.total.u128());
+    suite.fund(OWNER).unwrap();
+    assert_eq!(suite.query_distributable(), 50_000_000);
+    suite.distribute(RECIPIENT, None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 50_000_000);
+
+    suite.advance(50_000);
+    assert_eq!(suite.query_distributable(), 50_000_000);
+}
+
+/// A one second schedule starting in the future vests everything at
+/// once, while the tokens are already held by the contract.
+#[test]
+fn test_almost_instavest_in_the_future() {
+    let start = mock_env().block.time.plus_seconds(100);
+    let mut suite = SuiteBuilder::default()
+        .with_start_time(start)
+        .with_vesting_duration(1)
+        .build();
+    suite.spend(suite.total.u128());
+    suite.fund(OWNER).unwrap();
+
+    suite.advance(100);
+    assert_eq!(suite.time(), start);
+    assert_eq!(suite.query_distributable(), 0);
+
+    suite.advance(1);
+    assert_eq!(suite.query_distributable(), 100_000_000);
+    suite.distribute(RECIPIENT, None).unwrap();
+    assert_eq!(suite.balance(RECIPIENT), 100_000_000);
+}